@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::grpc::DiscoveredMapping;
+use crate::storage::FileId;
+
+/// Prefix substitution applied to mapping paths before searching, e.g.
+/// `/app/=/var/lib/containers/storage/overlay/<id>/merged/app/`.
+#[derive(Clone, Debug)]
+pub struct PathRewrite {
+    from: String,
+    to: PathBuf,
+}
+
+impl FromStr for PathRewrite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected FROM=TO, got `{s}`"))?;
+        if from.is_empty() || to.is_empty() {
+            return Err(format!("empty prefix in `{s}`"));
+        }
+        Ok(Self {
+            from: from.to_string(),
+            to: PathBuf::from(to),
+        })
+    }
+}
+
+impl PathRewrite {
+    fn apply(&self, path: &str) -> Option<PathBuf> {
+        let rest = path.strip_prefix(&self.from)?;
+        Some(self.to.join(rest.trim_start_matches('/')))
+    }
+}
+
+/// Locates local copies of binaries reported in profile mappings.
+///
+/// Automatic loading is only enabled when at least one search path or
+/// rewrite is configured, so nothing is read from disk implicitly.
+#[derive(Default)]
+pub struct SymbolSearch {
+    paths: Vec<PathBuf>,
+    rewrites: Vec<PathRewrite>,
+}
+
+impl SymbolSearch {
+    pub fn new(paths: Vec<PathBuf>, rewrites: Vec<PathRewrite>) -> Self {
+        Self { paths, rewrites }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.paths.is_empty() || !self.rewrites.is_empty()
+    }
+
+    /// Candidate local paths for a mapping path, most specific first:
+    /// rewritten prefixes, then each search path used as a sysroot, then
+    /// each search path joined with the basename.
+    pub fn candidates(&self, mapping_path: &str) -> Vec<PathBuf> {
+        let mut candidates: Vec<PathBuf> = self
+            .rewrites
+            .iter()
+            .filter_map(|r| r.apply(mapping_path))
+            .collect();
+
+        let relative = mapping_path.trim_start_matches('/');
+        let basename = Path::new(mapping_path).file_name();
+        for dir in &self.paths {
+            if !relative.is_empty() {
+                candidates.push(dir.join(relative));
+            }
+            if let Some(basename) = basename {
+                candidates.push(dir.join(basename));
+            }
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        candidates.retain(|p| seen.insert(p.clone()));
+        candidates
+    }
+
+    /// Find the first existing candidate whose file ID matches the one
+    /// reported by the sender (any candidate if the sender reported none).
+    pub fn locate(&self, mapping: &DiscoveredMapping) -> Option<(PathBuf, FileId)> {
+        self.candidates(&mapping.path)
+            .into_iter()
+            .filter(|p| p.is_file())
+            .find_map(|p| {
+                let file_id = FileId::from_path(&p).ok()?;
+                match mapping.file_id {
+                    Some(expected) if expected != file_id => None,
+                    _ => Some((p, file_id)),
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrite_requires_prefix_match() {
+        let rewrite: PathRewrite = "/app/=/srv/rootfs/app/".parse().unwrap();
+        assert_eq!(
+            rewrite.apply("/app/bin/server"),
+            Some(PathBuf::from("/srv/rootfs/app/bin/server"))
+        );
+        assert_eq!(rewrite.apply("/usr/bin/server"), None);
+        assert!("/app/".parse::<PathRewrite>().is_err());
+        assert!("=/srv".parse::<PathRewrite>().is_err());
+    }
+
+    #[test]
+    fn candidates_ordered_and_deduplicated() {
+        let search = SymbolSearch::new(
+            vec![PathBuf::from("/sysroot"), PathBuf::from("/sysroot")],
+            vec!["/app=/srv/app".parse().unwrap()],
+        );
+        assert_eq!(
+            search.candidates("/app/lib/libfoo.so"),
+            vec![
+                PathBuf::from("/srv/app/lib/libfoo.so"),
+                PathBuf::from("/sysroot/app/lib/libfoo.so"),
+                PathBuf::from("/sysroot/libfoo.so"),
+            ]
+        );
+    }
+
+    #[test]
+    fn disabled_without_configuration() {
+        assert!(!SymbolSearch::default().is_enabled());
+        assert!(SymbolSearch::default().candidates("/bin/ls").is_empty());
+    }
+}
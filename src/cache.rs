@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::storage::{FileId, ResolvedFrame};
+
+const FRAME_CACHE_CAPACITY: usize = 64 * 1024;
+const STRING_CACHE_CAPACITY: usize = 64 * 1024;
+
+/// Hit/miss counters and current sizes of the lookup caches.
+#[derive(Clone, Copy, Default)]
+pub struct CacheStats {
+    pub frame_hits: u64,
+    pub frame_misses: u64,
+    pub frame_entries: usize,
+    pub string_hits: u64,
+    pub string_misses: u64,
+    pub string_entries: usize,
+}
+
+impl CacheStats {
+    pub fn frame_hit_rate(&self) -> Option<f64> {
+        hit_rate(self.frame_hits, self.frame_misses)
+    }
+
+    pub fn string_hit_rate(&self) -> Option<f64> {
+        hit_rate(self.string_hits, self.string_misses)
+    }
+}
+
+fn hit_rate(hits: u64, misses: u64) -> Option<f64> {
+    let total = hits + misses;
+    (total > 0).then(|| hits as f64 / total as f64)
+}
+
+/// Bounded map approximating LRU with two generations.
+///
+/// Inserts go to `current`; once it holds half the capacity it becomes
+/// `previous` and the old `previous` is dropped. Hits in `previous` are
+/// promoted, so entries that keep being used survive rotation.
+struct GenCache<K, V> {
+    capacity: usize,
+    current: HashMap<K, V>,
+    previous: HashMap<K, V>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<K: Hash + Eq + Copy, V: Clone> GenCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            current: HashMap::new(),
+            previous: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        if let Some(v) = self.current.get(key) {
+            self.hits += 1;
+            return Some(v.clone());
+        }
+        if let Some(v) = self.previous.remove(key) {
+            self.hits += 1;
+            self.insert(*key, v.clone());
+            return Some(v);
+        }
+        self.misses += 1;
+        None
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.current.len() >= self.capacity / 2 {
+            self.previous = std::mem::take(&mut self.current);
+        }
+        self.current.insert(key, value);
+    }
+
+    fn retain(&mut self, keep: impl Fn(&K) -> bool) {
+        self.current.retain(|k, _| keep(k));
+        self.previous.retain(|k, _| keep(k));
+    }
+
+    fn len(&self) -> usize {
+        self.current.len() + self.previous.len()
+    }
+}
+
+/// In-memory caches in front of [`crate::storage::SymbolStore`] lookups.
+///
+/// Frames are keyed by `(FileId, address)` and include negative results
+/// (empty vectors), strings by `(FileId, string index)`. Both are dropped
+/// per file whenever that file's symbols are stored or removed.
+pub struct LookupCache {
+    frames: Mutex<GenCache<(FileId, u64), Vec<ResolvedFrame>>>,
+    strings: Mutex<GenCache<(FileId, u32), String>>,
+    /// Bumped on every invalidation so lookups that raced with it don't
+    /// repopulate the cache with stale results.
+    epoch: AtomicU64,
+}
+
+impl Default for LookupCache {
+    fn default() -> Self {
+        Self {
+            frames: Mutex::new(GenCache::new(FRAME_CACHE_CAPACITY)),
+            strings: Mutex::new(GenCache::new(STRING_CACHE_CAPACITY)),
+            epoch: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LookupCache {
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Acquire)
+    }
+
+    pub fn frames(&self, file_id: FileId, addr: u64) -> Option<Vec<ResolvedFrame>> {
+        self.frames.lock().unwrap().get(&(file_id, addr))
+    }
+
+    pub fn insert_frames(&self, epoch: u64, file_id: FileId, addr: u64, frames: &[ResolvedFrame]) {
+        let mut cache = self.frames.lock().unwrap();
+        if self.epoch() == epoch {
+            cache.insert((file_id, addr), frames.to_vec());
+        }
+    }
+
+    pub fn string(&self, file_id: FileId, idx: u32) -> Option<String> {
+        self.strings.lock().unwrap().get(&(file_id, idx))
+    }
+
+    pub fn insert_string(&self, epoch: u64, file_id: FileId, idx: u32, s: &str) {
+        let mut cache = self.strings.lock().unwrap();
+        if self.epoch() == epoch {
+            cache.insert((file_id, idx), s.to_owned());
+        }
+    }
+
+    /// Drop every cached entry belonging to `file_id`.
+    pub fn invalidate(&self, file_id: FileId) {
+        let mut frames = self.frames.lock().unwrap();
+        let mut strings = self.strings.lock().unwrap();
+        self.epoch.fetch_add(1, Ordering::AcqRel);
+        frames.retain(|(fid, _)| *fid != file_id);
+        strings.retain(|(fid, _)| *fid != file_id);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let frames = self.frames.lock().unwrap();
+        let strings = self.strings.lock().unwrap();
+        CacheStats {
+            frame_hits: frames.hits,
+            frame_misses: frames.misses,
+            frame_entries: frames.len(),
+            string_hits: strings.hits,
+            string_misses: strings.misses,
+            string_entries: strings.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gen_cache_stays_bounded_and_keeps_hot_entries() {
+        let mut cache = GenCache::new(4);
+        cache.insert(0, 0);
+        for i in 1..100 {
+            assert_eq!(cache.get(&0), Some(0));
+            cache.insert(i, i);
+            assert!(cache.len() <= 4);
+        }
+        assert_eq!(cache.get(&1), None);
+    }
+}
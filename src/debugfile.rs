@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use object::{Object, ReadCache};
+
+/// Debug directory searched when none is configured.
+pub const DEFAULT_DEBUG_DIR: &str = "/usr/lib/debug";
+
+/// References from a binary to its separate debug file.
+#[derive(Default)]
+struct DebugRefs {
+    has_dwarf: bool,
+    build_id: Option<Vec<u8>>,
+    debuglink: Option<(String, u32)>,
+}
+
+fn read_refs(path: &Path) -> Option<DebugRefs> {
+    let cache = ReadCache::new(File::open(path).ok()?);
+    let obj = object::File::parse(&cache).ok()?;
+    Some(DebugRefs {
+        has_dwarf: obj.section_by_name(".debug_info").is_some(),
+        build_id: obj.build_id().ok().flatten().map(<[u8]>::to_vec),
+        debuglink: obj
+            .gnu_debuglink()
+            .ok()
+            .flatten()
+            .map(|(name, crc)| (String::from_utf8_lossy(name).into_owned(), crc)),
+    })
+}
+
+/// `<dir>/.build-id/ab/cdef….debug` for each debug directory.
+fn build_id_candidates(build_id: &[u8], debug_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let Some((first, rest)) = build_id.split_first() else {
+        return Vec::new();
+    };
+    let rest: String = rest.iter().map(|b| format!("{b:02x}")).collect();
+    debug_dirs
+        .iter()
+        .map(|dir| {
+            dir.join(".build-id")
+                .join(format!("{first:02x}"))
+                .join(format!("{rest}.debug"))
+        })
+        .collect()
+}
+
+/// The GDB search order for a `.gnu_debuglink` name: next to the binary,
+/// in its `.debug` subdirectory, then mirrored under each debug directory.
+fn debuglink_candidates(binary: &Path, name: &str, debug_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let dir = binary.parent().unwrap_or(Path::new(""));
+    let mut candidates = vec![dir.join(name), dir.join(".debug").join(name)];
+    let relative = dir.strip_prefix("/").unwrap_or(dir);
+    candidates.extend(debug_dirs.iter().map(|d| d.join(relative).join(name)));
+    candidates
+}
+
+fn file_crc32(path: &Path) -> std::io::Result<u32> {
+    let mut file = File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buf)? {
+            0 => return Ok(hasher.finalize()),
+            n => hasher.update(&buf[..n]),
+        }
+    }
+}
+
+/// Locate the separate debug file of a stripped binary.
+///
+/// Build-id paths are tried first and must carry the same build ID;
+/// `.gnu_debuglink` candidates must match the recorded CRC32. Returns
+/// `None` if the binary already contains DWARF or nothing matches.
+pub fn locate(binary: &Path, debug_dirs: &[PathBuf]) -> Option<PathBuf> {
+    let refs = read_refs(binary)?;
+    if refs.has_dwarf {
+        return None;
+    }
+
+    if let Some(build_id) = &refs.build_id {
+        let found = build_id_candidates(build_id, debug_dirs)
+            .into_iter()
+            .filter(|p| p.is_file())
+            .find(|p| read_refs(p).and_then(|r| r.build_id).as_ref() == Some(build_id));
+        if found.is_some() {
+            return found;
+        }
+    }
+
+    let (name, crc) = refs.debuglink?;
+    let binary = binary.canonicalize().ok()?;
+    debuglink_candidates(&binary, &name, debug_dirs)
+        .into_iter()
+        .filter(|p| p.is_file() && p.canonicalize().ok().as_ref() != Some(&binary))
+        .find(|p| file_crc32(p).ok() == Some(crc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_file_candidates_follow_gdb_conventions() {
+        let dirs = [PathBuf::from("/usr/lib/debug"), PathBuf::from("/opt/dbg")];
+        assert_eq!(
+            build_id_candidates(&[0xab, 0xcd, 0x01], &dirs),
+            vec![
+                PathBuf::from("/usr/lib/debug/.build-id/ab/cd01.debug"),
+                PathBuf::from("/opt/dbg/.build-id/ab/cd01.debug"),
+            ]
+        );
+        assert!(build_id_candidates(&[], &dirs).is_empty());
+        assert_eq!(
+            debuglink_candidates(Path::new("/usr/bin/server"), "server.debug", &dirs),
+            vec![
+                PathBuf::from("/usr/bin/server.debug"),
+                PathBuf::from("/usr/bin/.debug/server.debug"),
+                PathBuf::from("/usr/lib/debug/usr/bin/server.debug"),
+                PathBuf::from("/opt/dbg/usr/bin/server.debug"),
+            ]
+        );
+    }
+}
@@ -20,6 +20,12 @@ pub enum Error {
     SymConv(#[from] symblib::symbconv::Error),
     #[error("storage error: {0}")]
     Storage(#[from] fjall::Error),
+    #[error("invalid folded stack on line {0}")]
+    FoldedStack(usize),
+    #[error("invalid rule on line {line}: {message}")]
+    Rule { line: usize, message: String },
+    #[error("cancelled")]
+    Cancelled,
     #[error("incompatible storage format at `{}`: delete the directory and restart", .0.display())]
     StorageVersionMismatch(PathBuf),
 }
@@ -1,240 +1,2169 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::Arc;
 
+use regex::Regex;
+
+use crate::rules::{FrameAction, FrameRules};
+
+/// Mapping and address of a native frame, kept so the frame can be
+/// symbolized again once symbols for its mapping change.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NativeFrame {
+    /// Basename of the mapped object.
+    pub mapping: String,
+    /// Address as reported by the sender.
+    pub address: u64,
+    /// Offset of `address` within the mapped file, set when the sender
+    /// reported a process virtual address inside the mapping's range.
+    pub file_offset: Option<u64>,
+    /// Owning process, recorded for anonymous (JIT) mappings only.
+    pub pid: Option<u32>,
+}
+
+/// One frame of a sampled stack.
 #[derive(Clone, Debug)]
-pub struct FlameNode {
+pub struct Frame {
+    /// Label used when the frame is not (or cannot be) symbolized.
     pub name: String,
+    /// Functions inlined into `name` at this location, outermost first.
+    pub inlined: Vec<String>,
+    pub native: Option<NativeFrame>,
+}
+
+impl Frame {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            inlined: Vec::new(),
+            native: None,
+        }
+    }
+}
+
+/// Interned frame name; only meaningful together with the [`Interner`]
+/// of the graph it came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NameId(u32);
+
+/// Index of a node in a [`FlameTree`] arena.
+pub type NodeId = u32;
+
+/// Frame names of one graph, each stored once.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    names: Vec<Arc<str>>,
+    index: HashMap<Arc<str>, NameId>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, name: &str) -> NameId {
+        if let Some(&id) = self.index.get(name) {
+            return id;
+        }
+        let id = NameId(self.names.len() as u32);
+        let name: Arc<str> = Arc::from(name);
+        self.names.push(Arc::clone(&name));
+        self.index.insert(name, id);
+        id
+    }
+
+    pub fn resolve(&self, id: NameId) -> &str {
+        &self.names[id.0 as usize]
+    }
+
+    pub fn get(&self, name: &str) -> Option<NameId> {
+        self.index.get(name).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Rough heap usage in bytes.
+    fn memory(&self) -> usize {
+        let strings: usize = self
+            .names
+            .iter()
+            .map(|n| n.len() + 2 * size_of::<usize>())
+            .sum();
+        strings
+            + self.names.capacity() * size_of::<Arc<str>>()
+            + self.index.capacity() * (size_of::<(Arc<str>, NameId)>() + 1)
+    }
+}
+
+/// Name of the nodes cold subtrees are merged into by
+/// [`FlameGraph::enforce_budget`].
+pub const OTHER: &str = "[other]";
+
+#[derive(Clone, Debug)]
+pub struct FlameNode {
+    pub name: NameId,
     pub total_value: i64,
     pub self_value: i64,
-    pub children: Vec<FlameNode>,
-    child_index: HashMap<String, usize>,
+    pub children: Vec<NodeId>,
+    /// Only reached through frames inlined into their caller.
+    pub inlined: bool,
 }
 
-impl FlameNode {
-    pub fn new(name: String) -> Self {
+/// Call tree stored as an arena of nodes; node [`FlameTree::ROOT`] is the
+/// root. Children are found by `(parent, name)` in a single index shared
+/// by the whole tree, so nodes carry no per-node maps.
+#[derive(Clone, Debug)]
+pub struct FlameTree {
+    nodes: Vec<FlameNode>,
+    child_index: HashMap<(NodeId, NameId), NodeId>,
+}
+
+impl FlameTree {
+    pub const ROOT: NodeId = 0;
+
+    fn new(root_name: NameId) -> Self {
         Self {
+            nodes: vec![FlameNode {
+                name: root_name,
+                total_value: 0,
+                self_value: 0,
+                children: Vec::new(),
+                inlined: false,
+            }],
+            child_index: HashMap::new(),
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &FlameNode {
+        &self.nodes[id as usize]
+    }
+
+    pub fn root(&self) -> &FlameNode {
+        self.node(Self::ROOT)
+    }
+
+    /// O(1) child lookup by name.
+    pub fn child_by_name(&self, parent: NodeId, name: NameId) -> Option<NodeId> {
+        self.child_index.get(&(parent, name)).copied()
+    }
+
+    /// Positional index of a child by name.
+    pub fn child_position(&self, parent: NodeId, name: NameId) -> Option<usize> {
+        let child = self.child_by_name(parent, name)?;
+        self.node(parent).children.iter().position(|&c| c == child)
+    }
+
+    fn child_or_insert(&mut self, parent: NodeId, name: NameId) -> NodeId {
+        if let Some(&id) = self.child_index.get(&(parent, name)) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(FlameNode {
             name,
             total_value: 0,
             self_value: 0,
             children: Vec::new(),
-            child_index: HashMap::new(),
+            inlined: false,
+        });
+        self.nodes[parent as usize].children.push(id);
+        self.child_index.insert((parent, name), id);
+        id
+    }
+
+    /// Like [`FlameTree::child_or_insert`], keeping the child marked
+    /// inlined only while everything added through it was.
+    fn inlined_child_or_insert(&mut self, parent: NodeId, name: NameId, inlined: bool) -> NodeId {
+        let new = self.nodes.len();
+        let node = self.child_or_insert(parent, name);
+        let flag = &mut self.nodes[node as usize].inlined;
+        *flag = inlined && (node as usize == new || *flag);
+        node
+    }
+
+    pub fn add_stack(&mut self, stack: &[NameId], value: i64) {
+        let mut node = Self::ROOT;
+        self.nodes[node as usize].total_value += value;
+        for &name in stack {
+            node = self.child_or_insert(node, name);
+            self.nodes[node as usize].total_value += value;
         }
+        self.nodes[node as usize].self_value += value;
     }
 
-    /// O(1) child lookup by name (returns reference).
-    pub fn child_by_name(&self, name: &str) -> Option<&FlameNode> {
-        self.child_index.get(name).map(|&idx| &self.children[idx])
+    /// [`FlameTree::add_stack`] for frames flagged as inlined.
+    fn add_inlined_stack(&mut self, stack: &[(NameId, bool)], value: i64) {
+        let mut node = Self::ROOT;
+        self.nodes[node as usize].total_value += value;
+        for &(name, inlined) in stack {
+            node = self.inlined_child_or_insert(node, name, inlined);
+            self.nodes[node as usize].total_value += value;
+        }
+        self.nodes[node as usize].self_value += value;
     }
 
-    /// O(1) positional index of a child by name.
-    pub fn child_position(&self, name: &str) -> Option<usize> {
-        self.child_index.get(name).copied()
+    /// Add every node of `other` below the matching path of this tree,
+    /// renaming with `rename` first; nodes that end up with the same name
+    /// under the same parent are merged.
+    pub fn merge_from(&mut self, other: &FlameTree, rename: impl Fn(NameId) -> NameId) {
+        self.merge_subtree(other, Self::ROOT, Self::ROOT, rename);
     }
 
-    pub fn add_stack(&mut self, stack: &[String], value: i64) {
-        self.total_value += value;
-        if stack.is_empty() {
-            self.self_value += value;
-            return;
+    /// Add the values of `other`'s node `src` to `dst`, and its descendants
+    /// below `dst`.
+    fn merge_subtree(
+        &mut self,
+        other: &FlameTree,
+        src: NodeId,
+        dst: NodeId,
+        rename: impl Fn(NameId) -> NameId,
+    ) {
+        let from = other.node(src);
+        self.nodes[dst as usize].total_value += from.total_value;
+        self.nodes[dst as usize].self_value += from.self_value;
+        let mut work = vec![(src, dst)];
+        while let Some((src, dst)) = work.pop() {
+            for &child in &other.node(src).children {
+                let from = other.node(child);
+                let to = self.inlined_child_or_insert(dst, rename(from.name), from.inlined);
+                let node = &mut self.nodes[to as usize];
+                node.total_value += from.total_value;
+                node.self_value += from.self_value;
+                work.push((child, to));
+            }
         }
-        let idx = if let Some(&idx) = self.child_index.get(&stack[0]) {
-            idx
-        } else {
-            let idx = self.children.len();
-            self.children.push(FlameNode::new(stack[0].clone()));
-            self.child_index.insert(stack[0].clone(), idx);
-            idx
-        };
-        self.children[idx].add_stack(&stack[1..], value);
     }
 
-    pub fn merge(&mut self, other: FlameNode) {
-        self.total_value += other.total_value;
-        self.self_value += other.self_value;
-        for other_child in other.children {
-            if let Some(&idx) = self.child_index.get(&other_child.name) {
-                self.children[idx].merge(other_child);
+    /// Add `other` to this tree, mapping each node's name through
+    /// `rename` and then `transform`: thread nodes are renamed or spliced
+    /// out, deeper frames are kept, hidden (spliced out) or folded into a
+    /// leaf, and repeated frames may be cut back to their first
+    /// occurrence. Stacks are then kept or dropped by the stack filter.
+    /// Nodes that end up with the same name under the same parent are
+    /// merged.
+    ///
+    /// Each kept source node's self value is added to the node it lands
+    /// on and to the totals of that node's ancestors, so totals stay exact
+    /// however the paths were reshaped.
+    fn derive_from(
+        &mut self,
+        other: &FlameTree,
+        rename: impl Fn(NameId) -> NameId,
+        transform: &mut Transform,
+    ) {
+        let focusing = transform.filter.focus.is_some();
+        let other_root = other.root();
+        let mut path = vec![Step {
+            node: Some(Self::ROOT),
+            name: other_root.name,
+            frame: false,
+            inlined: false,
+        }];
+        if !focusing {
+            self.add_to_path(&mut path, other_root.self_value);
+        }
+        // (source node, next child, path length, depth of its children,
+        // whether a focused frame is on the path)
+        let mut stack = vec![(Self::ROOT, 0, 1, 0, !focusing)];
+        let inlines = transform.inlines;
+        'children: while let Some(top) = stack.last_mut() {
+            let (src, next, len, depth, mut focused) = *top;
+            let Some(&child) = other.node(src).children.get(next) else {
+                stack.pop();
+                continue;
+            };
+            top.1 += 1;
+            path.truncate(len);
+            let from = other.node(child);
+            let name = rename(from.name);
+            let single = [name];
+            // A frame with inlined functions becomes a node per function,
+            // or just its own when inlines are folded.
+            let chain = match inlines.get(&name) {
+                Some(chain) if depth > 0 && transform.fold_inlines => &chain[..1],
+                Some(chain) if depth > 0 => chain.as_slice(),
+                _ => &single,
+            };
+            let mut descend = true;
+            for (i, &part) in chain.iter().enumerate() {
+                let mapped = if depth == 0 {
+                    transform.thread(part).map_or(Mapped::Hide, Mapped::Keep)
+                } else {
+                    transform.frame(part)
+                };
+                let (Mapped::Keep(name) | Mapped::Fold(name)) = mapped else {
+                    continue;
+                };
+                let (focus, ignore) = transform.matches(name);
+                if ignore {
+                    continue 'children;
+                }
+                if focus && !focused {
+                    focused = true;
+                    if transform.filter.reroot {
+                        path.truncate(1);
+                    }
+                }
+                let step = Step {
+                    node: None,
+                    name,
+                    frame: depth > 0,
+                    inlined: i > 0,
+                };
+                enter(&mut path, step, transform.recursion);
+                if let Mapped::Fold(_) = mapped {
+                    descend = false;
+                    break;
+                }
+            }
+            let value = if descend {
+                from.self_value
             } else {
-                let idx = self.children.len();
-                self.child_index.insert(other_child.name.clone(), idx);
-                self.children.push(other_child);
+                from.total_value
+            };
+            if focused && value != 0 {
+                self.add_to_path(&mut path, value);
+            }
+            if descend {
+                stack.push((child, 0, path.len(), depth + 1, focused));
+            }
+        }
+    }
+
+    /// Add `value` samples ending at the last step of `path`, creating the
+    /// nodes of steps not stored yet.
+    fn add_to_path(&mut self, path: &mut [Step], value: i64) {
+        let mut parent = Self::ROOT;
+        for step in path.iter_mut() {
+            let node = match step.node {
+                Some(node) => node,
+                None => {
+                    *step
+                        .node
+                        .insert(self.inlined_child_or_insert(parent, step.name, step.inlined))
+                }
+            };
+            self.nodes[node as usize].total_value += value;
+            parent = node;
+        }
+        self.nodes[parent as usize].self_value += value;
+    }
+
+    /// Order every node's children by descending total value.
+    pub fn sort(&mut self) {
+        for i in 0..self.nodes.len() {
+            let mut children = std::mem::take(&mut self.nodes[i].children);
+            children.sort_by_key(|&c| std::cmp::Reverse(self.nodes[c as usize].total_value));
+            self.nodes[i].children = children;
+        }
+    }
+
+    /// Order every node's children by `order`. Sorting is stable, so
+    /// frames with equal values keep their previous order.
+    fn sort_by_order(&mut self, order: FrameOrder, names: &Interner) {
+        for i in 0..self.nodes.len() {
+            let mut children = std::mem::take(&mut self.nodes[i].children);
+            match order {
+                FrameOrder::Value => {
+                    children.sort_by_key(|&c| std::cmp::Reverse(self.nodes[c as usize].total_value))
+                }
+                FrameOrder::Name => children.sort_by(|&a, &b| {
+                    names
+                        .resolve(self.node(a).name)
+                        .cmp(names.resolve(self.node(b).name))
+                }),
+                // Node ids grow as children are inserted.
+                FrameOrder::Insertion => children.sort_unstable(),
+            }
+            self.nodes[i].children = children;
+        }
+    }
+
+    /// Bottom-up copy of this tree: every stack ending in a node with self
+    /// value is added reversed, so leaf functions sit right below the root,
+    /// followed by their callers down to the thread.
+    pub fn inverted(&self) -> FlameTree {
+        let mut inverted = FlameTree::new(self.root().name);
+        let mut path = Vec::new();
+        let mut reversed = Vec::new();
+        let mut work: Vec<(NodeId, usize)> = self.root().children.iter().map(|&c| (c, 0)).collect();
+        while let Some((id, depth)) = work.pop() {
+            let node = self.node(id);
+            path.truncate(depth);
+            path.push((node.name, node.inlined));
+            if node.self_value > 0 {
+                reversed.clear();
+                reversed.extend(path.iter().rev());
+                inverted.add_inlined_stack(&reversed, node.self_value);
             }
+            work.extend(node.children.iter().map(|&c| (c, depth + 1)));
         }
+        inverted.sort();
+        inverted
     }
 
-    pub fn sort_recursive(&mut self) {
-        self.children
-            .sort_by(|a, b| b.total_value.cmp(&a.total_value));
-        self.rebuild_index();
-        for child in &mut self.children {
-            child.sort_recursive();
+    /// Callers and callees of every occurrence of `name`. Occurrences
+    /// nested in another one (recursion) only add their self value, so
+    /// each sample is counted once.
+    pub fn sandwich(&self, name: NameId) -> Sandwich {
+        let mut sandwich = Sandwich {
+            name,
+            callers: FlameTree::new(name),
+            callees: FlameTree::new(name),
+            self_value: 0,
+            total_value: 0,
+        };
+        let mut path = Vec::new();
+        let mut callers = Vec::new();
+        // (node, depth, below an occurrence of `name`)
+        let mut work: Vec<(NodeId, usize, bool)> = self
+            .root()
+            .children
+            .iter()
+            .map(|&c| (c, 0, false))
+            .collect();
+        while let Some((id, depth, nested)) = work.pop() {
+            let node = self.node(id);
+            path.truncate(depth);
+            let found = node.name == name;
+            if found {
+                sandwich.self_value += node.self_value;
+            }
+            if found && !nested {
+                sandwich.total_value += node.total_value;
+                callers.clear();
+                callers.extend(path.iter().rev());
+                sandwich
+                    .callers
+                    .add_inlined_stack(&callers, node.total_value);
+                sandwich.callees.merge_subtree(self, id, Self::ROOT, |n| n);
+            }
+            path.push((node.name, node.inlined));
+            work.extend(
+                node.children
+                    .iter()
+                    .map(|&c| (c, depth + 1, nested || found)),
+            );
+        }
+        sandwich.callers.sort();
+        sandwich.callees.sort();
+        sandwich
+    }
+
+    /// Per-function totals over the whole tree, excluding the thread level
+    /// when `threads` is set. A function's total only counts occurrences
+    /// not nested in another occurrence of itself, so recursion does not
+    /// inflate it.
+    pub fn functions(&self, threads: bool) -> Vec<FunctionStats> {
+        let first = usize::from(threads);
+        let mut stats: HashMap<NameId, FunctionStats> = HashMap::new();
+        let mut path = Vec::new();
+        let mut work: Vec<(NodeId, usize)> = self.root().children.iter().map(|&c| (c, 0)).collect();
+        while let Some((id, depth)) = work.pop() {
+            let node = self.node(id);
+            path.truncate(depth);
+            if depth >= first {
+                let entry = stats.entry(node.name).or_insert(FunctionStats {
+                    name: node.name,
+                    self_value: 0,
+                    total_value: 0,
+                    call_paths: 0,
+                });
+                entry.self_value += node.self_value;
+                entry.call_paths += 1;
+                if !path[first..].contains(&node.name) {
+                    entry.total_value += node.total_value;
+                }
+            }
+            path.push(node.name);
+            work.extend(node.children.iter().map(|&c| (c, depth + 1)));
         }
+        stats.into_values().collect()
     }
 
-    fn rebuild_index(&mut self) {
+    /// Names from the root down to the occurrence of `name` with the
+    /// largest total value.
+    pub fn hottest_path(&self, name: NameId) -> Option<Vec<NameId>> {
+        let mut best: Option<(i64, Vec<NameId>)> = None;
+        let mut path = Vec::new();
+        let mut work: Vec<(NodeId, usize)> = self.root().children.iter().map(|&c| (c, 0)).collect();
+        while let Some((id, depth)) = work.pop() {
+            let node = self.node(id);
+            path.truncate(depth);
+            path.push(node.name);
+            if node.name == name && best.as_ref().is_none_or(|(v, _)| node.total_value > *v) {
+                best = Some((node.total_value, path.clone()));
+            }
+            work.extend(node.children.iter().map(|&c| (c, depth + 1)));
+        }
+        best.map(|(_, path)| path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Rename every node in place. `rename` must not map two names to the
+    /// same one, as siblings are not merged.
+    fn rename_all(&mut self, rename: impl Fn(NameId) -> NameId) {
+        for node in &mut self.nodes {
+            node.name = rename(node.name);
+        }
         self.child_index.clear();
-        for (i, child) in self.children.iter().enumerate() {
-            self.child_index.insert(child.name.clone(), i);
+        for (parent, node) in self.nodes.iter().enumerate() {
+            for &child in &node.children {
+                let name = self.nodes[child as usize].name;
+                self.child_index.insert((parent as NodeId, name), child);
+            }
         }
     }
 
+    /// Rough heap usage in bytes.
+    pub fn memory(&self) -> usize {
+        let children: usize = self.nodes.iter().map(|n| n.children.capacity()).sum();
+        self.nodes.capacity() * size_of::<FlameNode>()
+            + children * size_of::<NodeId>()
+            + self.child_index.capacity() * (size_of::<((NodeId, NameId), NodeId)>() + 1)
+    }
+
+    /// Copy of the tree where every subtree with a total below `min_total`
+    /// is merged into an `other` leaf of its parent. Siblings `rename` maps
+    /// to the same name are kept or merged together, by their combined
+    /// total. Threads are always kept, so root and per-thread totals stay
+    /// exact.
+    fn pruned(
+        &self,
+        min_total: i64,
+        other: NameId,
+        rename: impl Fn(NameId) -> NameId,
+    ) -> FlameTree {
+        let mut tree = FlameTree::new(self.root().name);
+        tree.nodes[Self::ROOT as usize].total_value = self.root().total_value;
+        tree.nodes[Self::ROOT as usize].self_value = self.root().self_value;
+        let mut totals: HashMap<NameId, i64> = HashMap::new();
+        let mut work = vec![(Self::ROOT, Self::ROOT)];
+        while let Some((src, dst)) = work.pop() {
+            totals.clear();
+            for &child in &self.node(src).children {
+                let from = self.node(child);
+                *totals.entry(rename(from.name)).or_default() += from.total_value;
+            }
+            for &child in &self.node(src).children {
+                let from = self.node(child);
+                let keep = src == Self::ROOT || totals[&rename(from.name)] >= min_total;
+                let to = tree.child_or_insert(dst, if keep { from.name } else { other });
+                let to_node = &mut tree.nodes[to as usize];
+                to_node.total_value += from.total_value;
+                if keep {
+                    to_node.self_value += from.self_value;
+                    work.push((child, to));
+                } else {
+                    to_node.self_value += from.total_value;
+                }
+            }
+        }
+        tree
+    }
+
     #[allow(dead_code)]
     pub fn max_depth(&self) -> usize {
-        if self.children.is_empty() {
-            0
+        let mut max = 0;
+        let mut work = vec![(Self::ROOT, 0)];
+        while let Some((node, depth)) = work.pop() {
+            max = max.max(depth);
+            work.extend(self.node(node).children.iter().map(|&c| (c, depth + 1)));
+        }
+        max
+    }
+}
+
+/// Thread-name rewrite, `PATTERN=REPLACEMENT`, e.g. `-\d+$=-*` turns
+/// `worker-12` into `worker-*`. The replacement may use `$1`-style groups.
+#[derive(Clone, Debug)]
+pub struct ThreadRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl FromStr for ThreadRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, replacement) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected PATTERN=REPLACEMENT, got `{s}`"))?;
+        let pattern = Regex::new(pattern).map_err(|e| format!("invalid pattern in `{s}`: {e}"))?;
+        Ok(Self {
+            pattern,
+            replacement: replacement.to_string(),
+        })
+    }
+}
+
+/// How thread nodes, the first level below the root, are displayed.
+#[derive(Clone, Debug, Default)]
+pub struct ThreadView {
+    /// Applied in order to every thread name while `normalize` is set.
+    pub rules: Vec<ThreadRule>,
+    pub normalize: bool,
+    /// Drop the thread level, aggregating all threads.
+    pub collapse: bool,
+}
+
+impl ThreadView {
+    /// Rule used when none is configured: strip trailing digits.
+    pub fn default_rules() -> Vec<ThreadRule> {
+        vec![ThreadRule::from_str(r"\d+$=*").expect("valid default rule")]
+    }
+
+    fn normalize(&self, name: &str) -> String {
+        self.rules.iter().fold(name.to_string(), |name, rule| {
+            rule.pattern
+                .replace(&name, rule.replacement.as_str())
+                .into_owned()
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Mapped {
+    Hide,
+    Keep(NameId),
+    Fold(NameId),
+}
+
+/// Limit on the number of nodes a [`FlameGraph`] keeps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NodeBudget {
+    /// Nodes allowed before pruning; 0 disables the limit.
+    pub max_nodes: usize,
+    /// Subtrees below this share of the total are pruned first.
+    pub min_share: f64,
+}
+
+impl NodeBudget {
+    pub const DEFAULT_MAX_NODES: usize = 1_000_000;
+    pub const DEFAULT_MIN_SHARE: f64 = 0.001;
+}
+
+impl Default for NodeBudget {
+    fn default() -> Self {
+        Self {
+            max_nodes: Self::DEFAULT_MAX_NODES,
+            min_share: Self::DEFAULT_MIN_SHARE,
+        }
+    }
+}
+
+/// Left-to-right order of sibling frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameOrder {
+    /// Hottest first; frames move as live data arrives.
+    #[default]
+    Value,
+    /// Alphabetical, like classic flamegraphs.
+    Name,
+    /// First seen first, so frames stay put.
+    Insertion,
+}
+
+impl FrameOrder {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Value => "ordered by value",
+            Self::Name => "ordered by name",
+            Self::Insertion => "ordered as first seen",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Value => Self::Name,
+            Self::Name => Self::Insertion,
+            Self::Insertion => Self::Value,
+        }
+    }
+}
+
+/// How repeated frames on one stack are displayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Recursion {
+    /// Every repetition is its own node.
+    #[default]
+    Keep,
+    /// A frame calling itself directly is merged into one node.
+    Direct,
+    /// Any frame already on the stack (`A→B→A`) is merged into its first
+    /// occurrence.
+    Indirect,
+}
+
+impl Recursion {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Keep => "recursion kept",
+            Self::Direct => "direct recursion collapsed",
+            Self::Indirect => "all recursion collapsed",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Keep => Self::Direct,
+            Self::Direct => Self::Indirect,
+            Self::Indirect => Self::Keep,
+        }
+    }
+}
+
+/// pprof-style focus and ignore filters, matched against displayed names.
+#[derive(Clone, Debug, Default)]
+pub struct StackFilter {
+    /// Keep only stacks passing through a matching frame.
+    pub focus: Option<Regex>,
+    /// Drop stacks containing a matching frame.
+    pub ignore: Option<Regex>,
+    /// Re-root focused stacks at their outermost matching frame.
+    pub reroot: bool,
+}
+
+impl StackFilter {
+    pub fn is_active(&self) -> bool {
+        self.focus.is_some() || self.ignore.is_some()
+    }
+}
+
+/// Rough heap usage of a map's table in bytes, not counting what its
+/// entries own.
+fn map_memory<K, V>(map: &HashMap<K, V>) -> usize {
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+/// Intern a frame as one raw name. Frames with inlined functions get a
+/// name of their own for the whole chain, mapped in `inlines` to the
+/// functions it is shown as.
+fn intern_chain(
+    names: &mut Interner,
+    inlines: &mut HashMap<NameId, Vec<NameId>>,
+    name: &str,
+    inlined: &[String],
+) -> NameId {
+    if inlined.is_empty() {
+        return names.intern(name);
+    }
+    let mut key = name.to_string();
+    for callee in inlined {
+        key.push_str(" / ");
+        key.push_str(callee);
+    }
+    let id = names.intern(&key);
+    inlines.entry(id).or_insert_with(|| {
+        std::iter::once(name)
+            .chain(inlined.iter().map(String::as_str))
+            .map(|n| names.intern(n))
+            .collect()
+    });
+    id
+}
+
+/// Node on the path being derived; `node` is filled in once samples
+/// reach it, so stacks that are filtered out leave no empty nodes.
+struct Step {
+    node: Option<NodeId>,
+    name: NameId,
+    /// Below the thread level, so recursion collapsing may match it.
+    frame: bool,
+    /// Inlined into the previous step.
+    inlined: bool,
+}
+
+/// Extend `path` with `step`, or cut it back to an earlier occurrence of
+/// the frame when recursion is collapsed.
+fn enter(path: &mut Vec<Step>, step: Step, recursion: Recursion) {
+    let (name, frame) = (step.name, step.frame);
+    let repeat = match recursion {
+        _ if !frame => None,
+        Recursion::Keep => None,
+        Recursion::Direct => {
+            let last = &path[path.len() - 1];
+            (last.frame && last.name == name).then_some(path.len())
+        }
+        Recursion::Indirect => path
+            .iter()
+            .position(|step| step.frame && step.name == name)
+            .map(|i| i + 1),
+    };
+    match repeat {
+        Some(len) => path.truncate(len),
+        None => path.push(step),
+    }
+}
+
+/// Thread view, frame rules, recursion collapsing and stack filters
+/// applied while deriving a displayed tree; names are evaluated once each.
+struct Transform<'a> {
+    names: &'a mut Interner,
+    view: &'a ThreadView,
+    rules: &'a FrameRules,
+    recursion: Recursion,
+    filter: &'a StackFilter,
+    inlines: &'a HashMap<NameId, Vec<NameId>>,
+    fold_inlines: bool,
+    threads: HashMap<NameId, Option<NameId>>,
+    frames: HashMap<NameId, Mapped>,
+    matched: HashMap<NameId, (bool, bool)>,
+}
+
+impl<'a> Transform<'a> {
+    fn new(
+        names: &'a mut Interner,
+        view: &'a ThreadView,
+        rules: &'a FrameRules,
+        recursion: Recursion,
+        filter: &'a StackFilter,
+        inlines: &'a HashMap<NameId, Vec<NameId>>,
+        fold_inlines: bool,
+    ) -> Self {
+        Self {
+            names,
+            view,
+            rules,
+            recursion,
+            filter,
+            inlines,
+            fold_inlines,
+            threads: HashMap::new(),
+            frames: HashMap::new(),
+            matched: HashMap::new(),
+        }
+    }
+
+    /// Whether a displayed name matches the focus and the ignore filter.
+    fn matches(&mut self, name: NameId) -> (bool, bool) {
+        if !self.filter.is_active() {
+            return (false, false);
+        }
+        *self.matched.entry(name).or_insert_with(|| {
+            let name = self.names.resolve(name);
+            let hit = |re: &Option<Regex>| re.as_ref().is_some_and(|re| re.is_match(name));
+            (hit(&self.filter.focus), hit(&self.filter.ignore))
+        })
+    }
+
+    /// Displayed thread name; `None` when threads are collapsed.
+    fn thread(&mut self, name: NameId) -> Option<NameId> {
+        if let Some(&target) = self.threads.get(&name) {
+            return target;
+        }
+        let target = if self.view.collapse {
+            None
+        } else if self.view.normalize {
+            let normalized = self.view.normalize(self.names.resolve(name));
+            Some(self.names.intern(&normalized))
         } else {
-            1 + self
-                .children
-                .iter()
-                .map(|c| c.max_depth())
-                .max()
-                .unwrap_or(0)
+            Some(name)
+        };
+        self.threads.insert(name, target);
+        target
+    }
+
+    fn frame(&mut self, name: NameId) -> Mapped {
+        if let Some(&mapped) = self.frames.get(&name) {
+            return mapped;
         }
+        let mapped = match self.rules.apply(self.names.resolve(name)) {
+            FrameAction::Hide => Mapped::Hide,
+            FrameAction::Keep(Cow::Borrowed(_)) => Mapped::Keep(name),
+            FrameAction::Keep(Cow::Owned(renamed)) => Mapped::Keep(self.names.intern(&renamed)),
+            FrameAction::Fold(Cow::Borrowed(_)) => Mapped::Fold(name),
+            FrameAction::Fold(Cow::Owned(renamed)) => Mapped::Fold(self.names.intern(&renamed)),
+        };
+        self.frames.insert(name, mapped);
+        mapped
     }
 }
 
+/// Aggregated samples of one function across all its call paths.
+pub struct FunctionStats {
+    pub name: NameId,
+    pub self_value: i64,
+    pub total_value: i64,
+    /// Number of distinct call paths (tree nodes) the function appears at.
+    pub call_paths: usize,
+}
+
+/// One function seen from both sides, merged over all its occurrences.
+pub struct Sandwich {
+    pub name: NameId,
+    /// Rooted at the function, with its callers below it down to the thread.
+    pub callers: FlameTree,
+    /// Rooted at the function, with everything it calls below it.
+    pub callees: FlameTree,
+    pub self_value: i64,
+    pub total_value: i64,
+}
+
 #[derive(Clone, Debug)]
 pub struct FlameGraph {
-    pub root: FlameNode,
+    pub names: Interner,
+    /// Displayed tree, with native frames symbolized where possible.
+    pub root: FlameTree,
+    /// Same samples with native frames kept apart by mapping and address
+    /// until they are symbolized; `root` is derived from it by
+    /// [`FlameGraph::symbolize`].
+    raw: FlameTree,
+    /// Native frame behind each unsymbolized `mapping+0xaddr` label.
+    natives: HashMap<NameId, NativeFrame>,
+    /// Names given to native frames by symbolization.
+    renamed: HashMap<NameId, NameId>,
+    /// Native frames merged into the first frame resolving to the same
+    /// function, applied to samples added later.
+    aliases: HashMap<NameId, NameId>,
+    /// Functions of frames with inlined callees, outermost first, keyed by
+    /// the frame's raw name.
+    inlines: HashMap<NameId, Vec<NameId>>,
+    /// Thread normalization and collapsing applied to `root`.
+    pub thread_view: ThreadView,
+    /// Frame hiding, folding and renaming applied to `root`.
+    pub rules: FrameRules,
+    /// Recursion collapsing applied to `root`.
+    pub recursion: Recursion,
+    /// Focus and ignore filters applied to `root`.
+    pub filter: StackFilter,
+    /// Sibling order used by [`FlameGraph::sort`].
+    pub order: FrameOrder,
+    /// Show inlined functions as part of their caller instead of as nodes
+    /// of their own.
+    pub fold_inlines: bool,
 }
 
 impl FlameGraph {
     pub fn new() -> Self {
+        let mut names = Interner::default();
+        let all = names.intern("all");
+        Self {
+            names,
+            root: FlameTree::new(all),
+            raw: FlameTree::new(all),
+            natives: HashMap::new(),
+            renamed: HashMap::new(),
+            aliases: HashMap::new(),
+            inlines: HashMap::new(),
+            thread_view: ThreadView::default(),
+            rules: FrameRules::default(),
+            recursion: Recursion::default(),
+            filter: StackFilter::default(),
+            order: FrameOrder::default(),
+            fold_inlines: false,
+        }
+    }
+
+    pub fn name(&self, id: NameId) -> &str {
+        self.names.resolve(id)
+    }
+
+    /// Intern a frame's label, remembering its native frame for symbolization.
+    pub fn intern_frame(&mut self, frame: &Frame) -> NameId {
+        let id = intern_chain(
+            &mut self.names,
+            &mut self.inlines,
+            &frame.name,
+            &frame.inlined,
+        );
+        if let Some(native) = &frame.native {
+            self.natives.entry(id).or_insert_with(|| native.clone());
+        }
+        id
+    }
+
+    /// Record a stack of interned frames; it shows up in `root` after the
+    /// next [`FlameGraph::symbolize`].
+    pub fn add_stack(&mut self, stack: &[NameId], value: i64) {
+        self.raw.add_stack(stack, value);
+    }
+
+    /// Add `other`'s samples. Native frames this graph already knows keep
+    /// its own symbolization, which may be newer than `other`'s; new ones
+    /// resolving to a function it knows are merged into that function's
+    /// frame.
+    pub fn merge(&mut self, other: &FlameGraph) {
+        let samples = self.adopt(other);
+        self.add_samples(&samples);
+    }
+
+    /// Take over `other`'s names, native frames and symbolization as
+    /// [`FlameGraph::merge`] does, and return its samples in this graph's
+    /// names, ready for [`FlameGraph::add_samples`].
+    pub fn adopt(&mut self, other: &FlameGraph) -> FlameTree {
+        let mut remap: Vec<NameId> = other
+            .names
+            .names
+            .iter()
+            .map(|name| {
+                let id = self.names.intern(name);
+                self.aliases.get(&id).copied().unwrap_or(id)
+            })
+            .collect();
+        let mut functions: HashMap<NameId, NameId> =
+            self.renamed.iter().map(|(&id, &to)| (to, id)).collect();
+        for (from, native) in &other.natives {
+            let id = remap[from.0 as usize];
+            if self.natives.contains_key(&id) {
+                continue;
+            }
+            let Some(&to) = other.renamed.get(from) else {
+                self.natives.insert(id, native.clone());
+                continue;
+            };
+            match functions.entry(remap[to.0 as usize]) {
+                Entry::Occupied(first) => {
+                    self.aliases.insert(id, *first.get());
+                    remap[from.0 as usize] = *first.get();
+                }
+                Entry::Vacant(entry) => {
+                    self.natives.insert(id, native.clone());
+                    self.renamed.insert(id, *entry.key());
+                    entry.insert(id);
+                }
+            }
+        }
+        for (&from, chain) in &other.inlines {
+            self.inlines
+                .entry(remap[from.0 as usize])
+                .or_insert_with(|| chain.iter().map(|id| remap[id.0 as usize]).collect());
+        }
+        let mut samples = FlameTree::new(self.raw.root().name);
+        samples.merge_from(&other.raw, |id| remap[id.0 as usize]);
+        samples
+    }
+
+    /// Add samples in this graph's names, as returned by
+    /// [`FlameGraph::adopt`] or [`FlameGraph::samples`], to the raw and the
+    /// displayed tree.
+    pub fn add_samples(&mut self, samples: &FlameTree) {
+        let aliases = &self.aliases;
+        let alias = |id| aliases.get(&id).copied().unwrap_or(id);
+        self.raw.merge_from(samples, alias);
+        let renamed = &self.renamed;
+        let rename = |id| {
+            let id = alias(id);
+            renamed.get(&id).copied().unwrap_or(id)
+        };
+        let mut transform = Transform::new(
+            &mut self.names,
+            &self.thread_view,
+            &self.rules,
+            self.recursion,
+            &self.filter,
+            &self.inlines,
+            self.fold_inlines,
+        );
+        self.root.derive_from(samples, rename, &mut transform);
+    }
+
+    /// Replace every sample with the sum of `samples`, each in this graph's
+    /// names, keeping interned names and symbolization.
+    pub fn set_samples<'a>(&mut self, samples: impl IntoIterator<Item = &'a FlameTree>) {
+        let aliases = &self.aliases;
+        let mut raw = FlameTree::new(self.raw.root().name);
+        for tree in samples {
+            raw.merge_from(tree, |id| aliases.get(&id).copied().unwrap_or(id));
+        }
+        self.raw = raw;
+        self.rederive();
+    }
+
+    /// Received samples, with native frames in raw names.
+    pub fn samples(&self) -> &FlameTree {
+        &self.raw
+    }
+
+    /// Rebuild `root` from the raw tree, naming native frames with `resolve`
+    /// and falling back to their `mapping+0xaddr` label. `resolve` returns
+    /// the function followed by those inlined into it, outermost first.
+    /// Each distinct native frame is resolved once.
+    ///
+    /// Frames resolving to the same function are merged into the one seen
+    /// first for good, so later samples at other addresses of a function
+    /// don't add nodes; they show that frame's label if symbols go away.
+    pub fn symbolize(&mut self, resolve: impl FnMut(&NativeFrame) -> Option<Vec<String>>) {
+        self.resolve_natives(resolve);
+        self.rederive();
+    }
+
+    /// Like [`FlameGraph::symbolize`], but `root` is only rebuilt when a
+    /// native frame's name changed. Returns whether it was.
+    pub fn resymbolize(
+        &mut self,
+        resolve: impl FnMut(&NativeFrame) -> Option<Vec<String>>,
+    ) -> bool {
+        let changed = self.resolve_natives(resolve);
+        if changed {
+            self.rederive();
+        }
+        changed
+    }
+
+    /// Name native frames for [`FlameGraph::symbolize`], returning whether
+    /// any name changed.
+    fn resolve_natives(
+        &mut self,
+        mut resolve: impl FnMut(&NativeFrame) -> Option<Vec<String>>,
+    ) -> bool {
+        let mut natives: Vec<NameId> = self.natives.keys().copied().collect();
+        natives.sort_unstable();
+        let mut renamed = HashMap::new();
+        let mut functions = HashMap::new();
+        let mut aliases = HashMap::new();
+        for id in natives {
+            let resolved = resolve(&self.natives[&id]);
+            let Some((name, inlined)) = resolved.as_deref().and_then(<[_]>::split_first) else {
+                continue;
+            };
+            let to = intern_chain(&mut self.names, &mut self.inlines, name, inlined);
+            match functions.entry(to) {
+                Entry::Occupied(first) => {
+                    aliases.insert(id, *first.get());
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(id);
+                    renamed.insert(id, to);
+                }
+            }
+        }
+        let changed = renamed != self.renamed || !aliases.is_empty();
+        self.renamed = renamed;
+        if !aliases.is_empty() {
+            for id in aliases.keys() {
+                self.natives.remove(id);
+            }
+            for target in self.aliases.values_mut() {
+                if let Some(&first) = aliases.get(target) {
+                    *target = first;
+                }
+            }
+            let mut raw = FlameTree::new(self.raw.root().name);
+            raw.merge_from(&self.raw, |id| aliases.get(&id).copied().unwrap_or(id));
+            self.raw = raw;
+            self.aliases.extend(aliases);
+        }
+        changed
+    }
+
+    /// Native frames symbolization found no name for.
+    pub fn unresolved(&self) -> impl Iterator<Item = &NativeFrame> {
+        self.natives
+            .iter()
+            .filter(|(id, _)| !self.renamed.contains_key(id))
+            .map(|(_, native)| native)
+    }
+
+    /// Rebuild `root` from the raw tree with the symbolized names, the
+    /// thread view and the frame rules.
+    pub fn rederive(&mut self) {
+        let renamed = &self.renamed;
+        let rename = |id| renamed.get(&id).copied().unwrap_or(id);
+        let mut transform = Transform::new(
+            &mut self.names,
+            &self.thread_view,
+            &self.rules,
+            self.recursion,
+            &self.filter,
+            &self.inlines,
+            self.fold_inlines,
+        );
+        self.root = FlameTree::new(self.raw.root().name);
+        self.root.derive_from(&self.raw, rename, &mut transform);
+    }
+
+    /// Order the displayed tree's siblings by `order`.
+    pub fn sort(&mut self) {
+        self.root.sort_by_order(self.order, &self.names);
+    }
+
+    /// Bottom-up copy of the displayed tree, in the same order.
+    pub fn inverted(&self) -> FlameTree {
+        let mut inverted = self.root.inverted();
+        inverted.sort_by_order(self.order, &self.names);
+        inverted
+    }
+
+    /// Nodes stored for the received stacks, the number [`NodeBudget`]
+    /// limits.
+    pub fn node_count(&self) -> usize {
+        self.raw.len()
+    }
+
+    /// Rough heap usage of the trees, interned names and symbolization, in
+    /// bytes.
+    pub fn memory(&self) -> usize {
+        let natives: usize = self.natives.values().map(|n| n.mapping.len()).sum();
+        let inlines: usize = self
+            .inlines
+            .values()
+            .map(|chain| chain.capacity() * size_of::<NameId>())
+            .sum();
+        self.raw.memory()
+            + self.root.memory()
+            + self.names.memory()
+            + map_memory(&self.natives)
+            + natives
+            + map_memory(&self.renamed)
+            + map_memory(&self.aliases)
+            + map_memory(&self.inlines)
+            + inlines
+    }
+
+    /// Keep the graph within `budget`. Once it holds more nodes than
+    /// allowed, functions below `min_share` of the total at their call
+    /// path are merged into `[other]` nodes, doubling the share until three quarters of the
+    /// budget are left, so pruning doesn't repeat on every export.
+    /// Returns the share used, if anything was pruned.
+    ///
+    /// Names of pruned frames stay interned until [`FlameGraph::compact`].
+    pub fn enforce_budget(&mut self, budget: &NodeBudget) -> Option<f64> {
+        if budget.max_nodes == 0 || self.raw.len() <= budget.max_nodes {
+            return None;
+        }
+        let other = self.names.intern(OTHER);
+        let total = self.raw.root().total_value;
+        let target = budget.max_nodes - budget.max_nodes / 4;
+        let renamed = &self.renamed;
+        let rename = |id| renamed.get(&id).copied().unwrap_or(id);
+        let mut share = budget.min_share;
+        loop {
+            let min_total = ((total as f64 * share).ceil() as i64).max(1);
+            let pruned = self.raw.pruned(min_total, other, rename);
+            if pruned.len() <= target || share >= 1.0 {
+                self.raw = pruned;
+                break;
+            }
+            share *= 2.0;
+        }
+        self.rederive();
+        Some(share.min(1.0))
+    }
+
+    /// Whether the interned names outgrew `budget` enough to be worth a
+    /// [`FlameGraph::compact`]; native frames are interned per address, so
+    /// names keep growing even when the tree does not.
+    pub fn names_over_budget(&self, budget: &NodeBudget) -> bool {
+        budget.max_nodes > 0 && self.names.len() > 4 * budget.max_nodes
+    }
+
+    /// Drop interned names, native frames and symbolization that no node
+    /// of the raw tree, the displayed tree or `trees` refers to any more,
+    /// and renumber the remaining names, also in `trees`. Returns the
+    /// mapping from old to new ids, `None` for dropped names, for names
+    /// kept elsewhere.
+    pub fn compact(
+        &mut self,
+        trees: &mut [&mut FlameTree],
+    ) -> impl Fn(NameId) -> Option<NameId> + use<> {
+        let mut keep = vec![false; self.names.len()];
+        let all_trees = [&self.raw, &self.root]
+            .into_iter()
+            .chain(trees.iter().map(|tree| &**tree));
+        for tree in all_trees {
+            for node in &tree.nodes {
+                keep[node.name.0 as usize] = true;
+            }
+        }
+        let referenced: Vec<NameId> = (0..keep.len() as u32)
+            .map(NameId)
+            .filter(|id| keep[id.0 as usize])
+            .collect();
+        for id in referenced {
+            for name in std::iter::once(id).chain(self.renamed.get(&id).copied()) {
+                keep[name.0 as usize] = true;
+                for callee in self.inlines.get(&name).into_iter().flatten() {
+                    keep[callee.0 as usize] = true;
+                }
+            }
+        }
+
+        let mut names = Interner::default();
+        let remap: Vec<Option<NameId>> = self
+            .names
+            .names
+            .iter()
+            .zip(&keep)
+            .map(|(name, &kept)| kept.then(|| names.intern(name)))
+            .collect();
+        let map = |id: &NameId| remap[id.0 as usize];
+        self.natives = std::mem::take(&mut self.natives)
+            .into_iter()
+            .filter_map(|(id, native)| Some((map(&id)?, native)))
+            .collect();
+        self.renamed = std::mem::take(&mut self.renamed)
+            .into_iter()
+            .filter_map(|(id, to)| Some((map(&id)?, map(&to)?)))
+            .collect();
+        self.aliases = std::mem::take(&mut self.aliases)
+            .into_iter()
+            .filter_map(|(id, to)| Some((map(&id)?, map(&to)?)))
+            .collect();
+        self.inlines = std::mem::take(&mut self.inlines)
+            .into_iter()
+            .filter_map(|(id, chain)| {
+                Some((map(&id)?, chain.iter().map(map).collect::<Option<_>>()?))
+            })
+            .collect();
+        let rename = |id| map(&id).expect("referenced names are kept");
+        self.raw.rename_all(rename);
+        for tree in trees {
+            tree.rename_all(rename);
+        }
+        self.names = names;
+        self.rederive();
+        move |id| remap[id.0 as usize]
+    }
+
+    /// Write the received samples as folded stacks: one
+    /// `frame;frame;... value` line per call path with self samples,
+    /// threads first. Native frames are written symbolized and inlined
+    /// functions as frames of their own; the thread view, frame rules and
+    /// filters are not applied, so a baseline read back from the file gets
+    /// the current ones applied once.
+    pub fn write_folded(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut path: Vec<&str> = Vec::new();
+        let mut work = vec![(FlameTree::ROOT, 0)];
+        while let Some((id, depth)) = work.pop() {
+            let node = self.raw.node(id);
+            path.truncate(depth);
+            if id != FlameTree::ROOT {
+                let name = self.renamed.get(&node.name).copied().unwrap_or(node.name);
+                match self.inlines.get(&name) {
+                    Some(chain) if depth > 0 => path.extend(chain.iter().map(|&n| self.name(n))),
+                    _ => path.push(self.name(name)),
+                }
+            }
+            if node.self_value > 0 && !path.is_empty() {
+                writeln!(out, "{} {}", path.join(";"), node.self_value)?;
+            }
+            work.extend(node.children.iter().rev().map(|&c| (c, path.len())));
+        }
+        Ok(())
+    }
+
+    /// Parse folded stacks as written by [`FlameGraph::write_folded`] (or
+    /// any `stackcollapse` tool). Blank lines are skipped.
+    pub fn from_folded(input: &str) -> crate::Result<Self> {
+        let mut graph = Self::new();
+        let mut stack = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            let (frames, value) = line
+                .rsplit_once(' ')
+                .and_then(|(frames, value)| Some((frames, value.parse::<i64>().ok()?)))
+                .ok_or(crate::Error::FoldedStack(i + 1))?;
+            stack.clear();
+            stack.extend(frames.split(';').map(|f| graph.names.intern(f)));
+            graph.add_stack(&stack, value);
+        }
+        graph.symbolize(|_| None);
+        graph.root.sort();
+        Ok(graph)
+    }
+}
+
+/// Baseline values matched to the nodes of a current tree by frame-name path.
+pub struct Diff {
+    baseline: Vec<Option<(i64, i64)>>,
+    pub total: i64,
+    pub base_total: i64,
+}
+
+impl Diff {
+    pub fn new(
+        current: &FlameTree,
+        names: &Interner,
+        base: &FlameTree,
+        base_names: &Interner,
+    ) -> Self {
+        let mut baseline = vec![None; current.nodes.len()];
+        baseline[FlameTree::ROOT as usize] =
+            Some((base.root().self_value, base.root().total_value));
+        let mut work = vec![(FlameTree::ROOT, FlameTree::ROOT)];
+        while let Some((node, base_node)) = work.pop() {
+            for &child in &current.node(node).children {
+                let name = names.resolve(current.node(child).name);
+                let Some(base_child) = base_names
+                    .get(name)
+                    .and_then(|id| base.child_by_name(base_node, id))
+                else {
+                    continue;
+                };
+                let b = base.node(base_child);
+                baseline[child as usize] = Some((b.self_value, b.total_value));
+                work.push((child, base_child));
+            }
+        }
         Self {
-            root: FlameNode::new("all".to_string()),
+            baseline,
+            total: current.root().total_value,
+            base_total: base.root().total_value,
         }
     }
 
-    pub fn add_stack(&mut self, stack: &[String], value: i64) {
-        self.root.add_stack(stack, value);
+    /// Baseline `(self, total)` of a current node, if the baseline has it.
+    pub fn baseline(&self, node: NodeId) -> Option<(i64, i64)> {
+        self.baseline.get(node as usize).copied().flatten()
+    }
+
+    /// Change of a node's share of all samples, from -1 (gone) to 1 (new),
+    /// relative to the larger of the two shares.
+    pub fn change(&self, node: NodeId, total_value: i64) -> f64 {
+        let after = share(total_value, self.total);
+        let before = self
+            .baseline(node)
+            .map_or(0.0, |(_, t)| share(t, self.base_total));
+        share_change(before, after)
     }
 }
 
-pub fn get_zoom_node<'a>(root: &'a FlameNode, zoom_path: &[String]) -> &'a FlameNode {
-    let mut node = root;
-    for name in zoom_path {
-        if let Some(child) = node.child_by_name(name) {
+/// Difference between two shares relative to the larger one, from -1 to 1.
+pub fn share_change(before: f64, after: f64) -> f64 {
+    let larger = after.max(before);
+    if larger > 0.0 {
+        (after - before) / larger
+    } else {
+        0.0
+    }
+}
+
+pub fn share(value: i64, total: i64) -> f64 {
+    if total > 0 {
+        value as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+pub fn get_zoom_node(tree: &FlameTree, zoom_path: &[NameId]) -> NodeId {
+    let mut node = FlameTree::ROOT;
+    for &name in zoom_path {
+        if let Some(child) = tree.child_by_name(node, name) {
             node = child;
         }
     }
     node
 }
 
-pub fn get_node<'a>(root: &'a FlameNode, index_path: &[usize]) -> &'a FlameNode {
-    let mut node = root;
+pub fn get_node(tree: &FlameTree, from: NodeId, index_path: &[usize]) -> NodeId {
+    let mut node = from;
     for &idx in index_path {
-        if idx < node.children.len() {
-            node = &node.children[idx];
+        if let Some(&child) = tree.node(node).children.get(idx) {
+            node = child;
         }
     }
     node
 }
 
 pub struct FrameRect {
+    pub node: NodeId,
     pub x: u16,
     pub width: u16,
     pub depth: usize,
-    pub name: String,
+    pub name: NameId,
     pub self_value: i64,
     pub total_value: i64,
     pub palette_index: usize,
+    pub inlined: bool,
 }
 
-pub fn thread_rank(root: &FlameNode, thread_name: &str) -> usize {
-    root.child_position(thread_name).unwrap_or(0)
+pub fn thread_rank(tree: &FlameTree, thread: NameId) -> usize {
+    tree.child_position(FlameTree::ROOT, thread).unwrap_or(0)
 }
 
 pub fn layout_frames(
-    node: &FlameNode,
+    tree: &FlameTree,
+    node: NodeId,
     area_width: u16,
     forced_palette: Option<usize>,
 ) -> Vec<FrameRect> {
-    if node.total_value <= 0 {
+    let total_value = tree.node(node).total_value;
+    if total_value <= 0 {
         return Vec::new();
     }
-    let scale = area_width as f64 / node.total_value as f64;
+    let scale = area_width as f64 / total_value as f64;
     let mut frames = Vec::new();
-    layout_recursive(node, 0.0, 0, scale, forced_palette, &mut frames);
-    frames
-}
-
-fn layout_recursive(
-    node: &FlameNode,
-    x_float: f64,
-    depth: usize,
-    scale: f64,
-    palette: Option<usize>,
-    frames: &mut Vec<FrameRect>,
-) {
-    let x_end = x_float + node.total_value as f64 * scale;
-    let x = x_float.round() as u16;
-    let width = (x_end.round() as u16).saturating_sub(x);
+    // (node, x, depth, palette)
+    let mut work = vec![(node, 0.0, 0, forced_palette)];
+    while let Some((id, x_float, depth, palette)) = work.pop() {
+        let node = tree.node(id);
+        let x_end = x_float + node.total_value as f64 * scale;
+        let x = x_float.round() as u16;
+        let width = (x_end.round() as u16).saturating_sub(x);
 
-    if width == 0 {
-        return;
-    }
+        if width == 0 {
+            continue;
+        }
 
-    let palette_index = palette.unwrap_or(0);
+        let palette_index = palette.unwrap_or(0);
 
-    frames.push(FrameRect {
-        x,
-        width,
-        depth,
-        name: node.name.clone(),
-        self_value: node.self_value,
-        total_value: node.total_value,
-        palette_index,
-    });
+        frames.push(FrameRect {
+            node: id,
+            x,
+            width,
+            depth,
+            name: node.name,
+            self_value: node.self_value,
+            total_value: node.total_value,
+            palette_index,
+            inlined: node.inlined,
+        });
 
-    let mut child_x = x_float;
-    for (i, child) in node.children.iter().enumerate() {
-        let child_palette = if depth == 0 && palette.is_none() {
-            Some(i)
-        } else {
-            Some(palette_index)
-        };
-        layout_recursive(child, child_x, depth + 1, scale, child_palette, frames);
-        child_x += child.total_value as f64 * scale;
+        let mut child_x = x_float;
+        for (i, &child) in node.children.iter().enumerate() {
+            let child_palette = if depth == 0 && palette.is_none() {
+                Some(i)
+            } else {
+                Some(palette_index)
+            };
+            work.push((child, child_x, depth + 1, child_palette));
+            child_x += tree.node(child).total_value as f64 * scale;
+        }
     }
+    frames
 }
 
 pub fn cursor_frame_rect(
-    zoom_root: &FlameNode,
+    tree: &FlameTree,
+    zoom_root: NodeId,
     cursor_path: &[usize],
     area_width: u16,
     forced_palette: Option<usize>,
 ) -> Option<FrameRect> {
-    if zoom_root.total_value <= 0 {
+    let mut id = zoom_root;
+    let mut node = tree.node(id);
+    if node.total_value <= 0 {
         return None;
     }
-    let scale = area_width as f64 / zoom_root.total_value as f64;
-    let mut node = zoom_root;
+    let scale = area_width as f64 / node.total_value as f64;
     let mut x_acc = 0.0;
     let mut palette_index = forced_palette.unwrap_or(0);
 
     for (step, &idx) in cursor_path.iter().enumerate() {
-        for i in 0..idx.min(node.children.len()) {
-            x_acc += node.children[i].total_value as f64 * scale;
+        for &sibling in node.children.iter().take(idx) {
+            x_acc += tree.node(sibling).total_value as f64 * scale;
         }
-        if idx < node.children.len() {
-            if step == 0 && forced_palette.is_none() {
-                palette_index = idx;
-            }
-            node = &node.children[idx];
-        } else {
-            return None;
+        let &child = node.children.get(idx)?;
+        if step == 0 && forced_palette.is_none() {
+            palette_index = idx;
         }
+        id = child;
+        node = tree.node(child);
     }
 
     let x = x_acc.round() as u16;
     let width = (node.total_value as f64 * scale).round().max(1.0) as u16;
 
     Some(FrameRect {
+        node: id,
         x,
         width,
         depth: cursor_path.len(),
-        name: node.name.clone(),
+        name: node.name,
         self_value: node.self_value,
         total_value: node.total_value,
         palette_index,
+        inlined: node.inlined,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn native(mapping: &str, address: u64) -> Frame {
+        Frame {
+            name: format!("{mapping}+0x{address:x}"),
+            inlined: Vec::new(),
+            native: Some(NativeFrame {
+                mapping: mapping.to_string(),
+                address,
+                file_offset: None,
+                pid: None,
+            }),
+        }
+    }
+
+    fn add(graph: &mut FlameGraph, stack: &[Frame], value: i64) {
+        let ids: Vec<NameId> = stack.iter().map(|f| graph.intern_frame(f)).collect();
+        graph.add_stack(&ids, value);
+    }
+
+    fn child_names(graph: &FlameGraph, node: NodeId) -> Vec<&str> {
+        let tree = &graph.root;
+        tree.node(node)
+            .children
+            .iter()
+            .map(|&c| graph.name(tree.node(c).name))
+            .collect()
+    }
+
+    #[test]
+    fn symbolize_merges_and_reverts_native_frames() {
+        let mut graph = FlameGraph::new();
+        add(
+            &mut graph,
+            &[Frame::new("main".into()), native("libfoo.so", 0x10)],
+            2,
+        );
+        add(
+            &mut graph,
+            &[Frame::new("main".into()), native("libfoo.so", 0x20)],
+            3,
+        );
+
+        graph.symbolize(|_| None);
+        let main = graph.root.root().children[0];
+        assert_eq!(
+            child_names(&graph, main),
+            ["libfoo.so+0x10", "libfoo.so+0x20"]
+        );
+        assert_eq!(graph.unresolved().count(), 2);
+
+        graph.symbolize(|frame| (frame.mapping == "libfoo.so").then(|| vec!["foo".to_string()]));
+        let main = graph.root.root().children[0];
+        assert_eq!(child_names(&graph, main), ["foo"]);
+        let foo = graph.root.node(main).children[0];
+        assert_eq!(graph.root.node(foo).total_value, 5);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.unresolved().count(), 0);
+
+        // Later samples at other addresses of `foo` join its node.
+        let mut more = FlameGraph::new();
+        add(
+            &mut more,
+            &[Frame::new("main".into()), native("libfoo.so", 0x30)],
+            1,
+        );
+        more.symbolize(|_| Some(vec!["foo".to_string()]));
+        graph.merge(&more);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.root.node(foo).total_value, 6);
+
+        graph.symbolize(|_| None);
+        let main = graph.root.root().children[0];
+        assert_eq!(child_names(&graph, main), ["libfoo.so+0x10"]);
+        let foo = graph.root.node(main).children[0];
+        assert_eq!(graph.root.node(foo).total_value, 6);
+    }
+
+    #[test]
+    fn resymbolize_rebuilds_only_on_name_changes() {
+        let mut graph = FlameGraph::new();
+        add(
+            &mut graph,
+            &[Frame::new("main".into()), native("libfoo.so", 0x10)],
+            2,
+        );
+        graph.symbolize(|_| None);
+
+        assert!(!graph.resymbolize(|_| None));
+        assert!(graph.resymbolize(|_| Some(vec!["foo".to_string()])));
+        assert!(!graph.resymbolize(|_| Some(vec!["foo".to_string()])));
+        let main = graph.root.root().children[0];
+        assert_eq!(child_names(&graph, main), ["foo"]);
+
+        assert!(graph.resymbolize(|_| None));
+        let main = graph.root.root().children[0];
+        assert_eq!(child_names(&graph, main), ["libfoo.so+0x10"]);
+    }
+
+    #[test]
+    fn merge_remaps_names_between_graphs() {
+        let mut a = FlameGraph::new();
+        add(
+            &mut a,
+            &[Frame::new("t1".into()), Frame::new("f".into())],
+            1,
+        );
+        a.symbolize(|_| None);
+        let mut b = FlameGraph::new();
+        add(&mut b, &[Frame::new("t2".into())], 4);
+        add(
+            &mut b,
+            &[Frame::new("t1".into()), Frame::new("f".into())],
+            2,
+        );
+        b.symbolize(|_| None);
+
+        a.merge(&b);
+        a.root.sort();
+
+        assert_eq!(a.root.root().total_value, 7);
+        assert_eq!(child_names(&a, FlameTree::ROOT), ["t2", "t1"]);
+        let t1 = a.root.root().children[1];
+        let f = a.root.node(t1).children[0];
+        assert_eq!(a.root.node(f).self_value, 3);
+    }
+
+    #[test]
+    fn merge_keeps_own_symbolization() {
+        let stack = [Frame::new("t".into()), native("libfoo.so", 0x10)];
+        let mut graph = FlameGraph::new();
+        add(&mut graph, &stack, 1);
+        graph.symbolize(|_| None);
+
+        // Symbolized before its symbols were removed here.
+        let mut stale = FlameGraph::new();
+        add(&mut stale, &stack, 2);
+        stale.symbolize(|_| Some(vec!["foo".to_string()]));
+        graph.merge(&stale);
+        let t = graph.root.root().children[0];
+        assert_eq!(child_names(&graph, t), ["libfoo.so+0x10"]);
+
+        graph.rederive();
+        let t = graph.root.root().children[0];
+        assert_eq!(child_names(&graph, t), ["libfoo.so+0x10"]);
+        assert_eq!(graph.root.node(t).total_value, 3);
+    }
+
+    #[test]
+    fn inverted_puts_leaves_first() {
+        let mut graph = FlameGraph::new();
+        let frames = |names: &[&str]| -> Vec<Frame> {
+            names.iter().map(|n| Frame::new(n.to_string())).collect()
+        };
+        let main_with_a = Frame {
+            name: "main".into(),
+            inlined: vec!["a".into()],
+            native: None,
+        };
+        add(
+            &mut graph,
+            &[
+                Frame::new("t".into()),
+                main_with_a,
+                Frame::new("memcpy".into()),
+            ],
+            3,
+        );
+        add(&mut graph, &frames(&["t", "main", "b", "memcpy"]), 2);
+        add(&mut graph, &frames(&["t", "main", "b"]), 4);
+        graph.symbolize(|_| None);
+
+        let inverted = graph.root.inverted();
+        let names = |node: NodeId| -> Vec<(&str, i64)> {
+            inverted
+                .node(node)
+                .children
+                .iter()
+                .map(|&c| {
+                    (
+                        graph.name(inverted.node(c).name),
+                        inverted.node(c).total_value,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(inverted.root().total_value, 9);
+        assert_eq!(names(FlameTree::ROOT), [("memcpy", 5), ("b", 4)]);
+        let memcpy = inverted.root().children[0];
+        assert_eq!(names(memcpy), [("a", 3), ("b", 2)]);
+        let a = inverted.node(memcpy).children[0];
+        assert!(inverted.node(a).inlined && !inverted.node(memcpy).inlined);
+    }
+
+    #[test]
+    fn folded_round_trip_and_diff() {
+        let base = FlameGraph::from_folded("t;main;a 6\nt;main;b 2\n\nt;main 2\n").unwrap();
+        let mut out = Vec::new();
+        base.write_folded(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "t;main 2\nt;main;a 6\nt;main;b 2\n"
+        );
+        assert!(matches!(
+            FlameGraph::from_folded("t;main 1\nt;main x\n"),
+            Err(crate::Error::FoldedStack(2))
+        ));
+
+        let current = FlameGraph::from_folded("t;main;a 2\nt;main;b 4\nt;main;c 4\n").unwrap();
+        let diff = Diff::new(&current.root, &current.names, &base.root, &base.names);
+        let thread = current.root.root().children[0];
+        let main = current.root.node(thread).children[0];
+        let child = |name: &str| {
+            let id = current.names.get(name).unwrap();
+            current.root.child_by_name(main, id).unwrap()
+        };
+        assert_eq!(diff.baseline(child("a")), Some((6, 6)));
+        assert_eq!(diff.baseline(child("c")), None);
+        assert!((diff.change(child("a"), 2) + 2.0 / 3.0).abs() < 1e-9);
+        assert!((diff.change(child("b"), 4) - 0.5).abs() < 1e-9);
+        assert_eq!(diff.change(child("c"), 4), 1.0);
+        assert_eq!(diff.change(main, 10), 0.0);
+    }
+
+    #[test]
+    fn folded_export_is_symbolized_but_untransformed() {
+        let mut graph = FlameGraph::new();
+        let thread = Frame::new("worker-1".into());
+        let inlined = Frame {
+            name: "f".into(),
+            inlined: vec!["g".into()],
+            native: None,
+        };
+        add(&mut graph, &[thread.clone(), inlined], 3);
+        add(&mut graph, &[thread, native("libfoo.so", 0x10)], 2);
+        graph.symbolize(|_| Some(vec!["a".into(), "b".into()]));
+        graph.thread_view.collapse = true;
+        graph.fold_inlines = true;
+        graph.rederive();
+
+        let mut out = Vec::new();
+        graph.write_folded(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "worker-1;f;g 3\nworker-1;a;b 2\n"
+        );
+    }
+
+    #[test]
+    fn sandwich_merges_every_occurrence() {
+        let mut graph = FlameGraph::from_folded(
+            "t1;main;handler;encode 2\nt2;worker;encode;encode 4\nt2;worker 1\n",
+        )
+        .unwrap();
+        let encode_with_write = Frame {
+            name: "encode".into(),
+            inlined: vec!["write".into()],
+            native: None,
+        };
+        add(
+            &mut graph,
+            &[
+                Frame::new("t1".into()),
+                Frame::new("main".into()),
+                encode_with_write,
+            ],
+            3,
+        );
+        graph.symbolize(|_| None);
+        let encode = graph.names.get("encode").unwrap();
+        let sandwich = graph.root.sandwich(encode);
+
+        assert_eq!((sandwich.self_value, sandwich.total_value), (6, 9));
+        let names = |tree: &FlameTree, node: NodeId| -> Vec<(&str, i64)> {
+            tree.node(node)
+                .children
+                .iter()
+                .map(|&c| (graph.name(tree.node(c).name), tree.node(c).total_value))
+                .collect()
+        };
+        let callers = &sandwich.callers;
+        assert_eq!(callers.root().total_value, 9);
+        assert_eq!(
+            names(callers, FlameTree::ROOT),
+            [("worker", 4), ("main", 3), ("handler", 2)]
+        );
+        let callees = &sandwich.callees;
+        assert_eq!(callees.root().total_value, 9);
+        assert_eq!(
+            names(callees, FlameTree::ROOT),
+            [("encode", 4), ("write", 3)]
+        );
+        assert!(callees.node(callees.root().children[1]).inlined);
+    }
+
+    #[test]
+    fn functions_count_recursion_once() {
+        let graph = FlameGraph::from_folded(
+            "t1;main;parse;parse;lex 3\nt1;main;parse 1\nt2;parse 2\nt2;main;lex 5\n",
+        )
+        .unwrap();
+        let mut stats: Vec<(&str, i64, i64, usize)> = graph
+            .root
+            .functions(true)
+            .iter()
+            .map(|f| {
+                (
+                    graph.name(f.name),
+                    f.self_value,
+                    f.total_value,
+                    f.call_paths,
+                )
+            })
+            .collect();
+        stats.sort();
+        assert_eq!(
+            stats,
+            [("lex", 8, 8, 2), ("main", 0, 9, 2), ("parse", 3, 6, 3)]
+        );
+
+        let lex = graph.names.get("lex").unwrap();
+        let path: Vec<&str> = graph
+            .root
+            .hottest_path(lex)
+            .unwrap()
+            .into_iter()
+            .map(|n| graph.name(n))
+            .collect();
+        assert_eq!(path, ["t2", "main", "lex"]);
+    }
+
+    #[test]
+    fn thread_view_normalizes_and_collapses_threads() {
+        let mut graph =
+            FlameGraph::from_folded("worker-1;run 2\nworker-12;run 3\nmain;run 1\nmain 6\n")
+                .unwrap();
+        assert_eq!(child_names(&graph, FlameTree::ROOT).len(), 3);
+
+        graph.thread_view = ThreadView {
+            rules: vec!["-\\d+$=-*".parse().unwrap()],
+            normalize: true,
+            collapse: false,
+        };
+        graph.rederive();
+        graph.root.sort();
+        assert_eq!(child_names(&graph, FlameTree::ROOT), ["main", "worker-*"]);
+        let workers = graph.root.root().children[1];
+        assert_eq!(graph.root.node(workers).total_value, 5);
+
+        graph.thread_view.collapse = true;
+        graph.rederive();
+        assert_eq!(child_names(&graph, FlameTree::ROOT), ["run"]);
+        assert_eq!(graph.root.root().self_value, 6);
+        assert_eq!(graph.root.root().total_value, 12);
+
+        assert!("(=x".parse::<ThreadRule>().is_err());
+        assert_eq!(ThreadView::default().normalize("worker-7"), "worker-7");
+        let defaults = ThreadView {
+            rules: ThreadView::default_rules(),
+            ..Default::default()
+        };
+        assert_eq!(defaults.normalize("worker-7"), "worker-*");
+    }
+
+    #[test]
+    fn frame_rules_hide_fold_and_rename_existing_and_merged_data() {
+        let mut graph = FlameGraph::from_folded(
+            "t;__libc_start_main;main;serde::de::a;x 2\nt;__libc_start_main;main;app::run 3\n",
+        )
+        .unwrap();
+        graph.rules =
+            FrameRules::parse("hide ^__libc_start_main$\nfold ^serde::\nrename ^app::=crate::\n")
+                .unwrap();
+        graph.rederive();
+        graph.root.sort();
+
+        let t = graph.root.root().children[0];
+        assert_eq!(child_names(&graph, t), ["main"]);
+        let main = graph.root.node(t).children[0];
+        assert_eq!(child_names(&graph, main), ["crate::run", "serde::de::a"]);
+        let serde = graph.root.node(main).children[1];
+        assert!(graph.root.node(serde).children.is_empty());
+        assert_eq!(graph.root.node(serde).self_value, 2);
+
+        let other = FlameGraph::from_folded("t;__libc_start_main;main;app::run 1\n").unwrap();
+        graph.merge(&other);
+        let run = graph.root.node(main).children[0];
+        assert_eq!(graph.root.node(run).total_value, 4);
+    }
+
+    #[test]
+    fn recursion_collapses_direct_and_indirect_repetitions() {
+        let mut graph =
+            FlameGraph::from_folded("t;parse;parse;parse;lex 3\nt;parse 1\nt;a;b;a;b;c 2\n")
+                .unwrap();
+        let node_named = |graph: &FlameGraph, parent: NodeId, name: &str| {
+            let tree = &graph.root;
+            tree.child_by_name(parent, graph.names.get(name).unwrap())
+                .unwrap()
+        };
+
+        graph.recursion = Recursion::Direct;
+        graph.rederive();
+        let t = graph.root.root().children[0];
+        let parse = node_named(&graph, t, "parse");
+        let node = graph.root.node(parse);
+        assert_eq!((node.total_value, node.self_value), (4, 1));
+        assert_eq!(child_names(&graph, parse), ["lex"]);
+        let a = node_named(&graph, t, "a");
+        let b = node_named(&graph, a, "b");
+        assert_eq!(child_names(&graph, b), ["a"]);
+
+        let other = FlameGraph::from_folded("t;parse;parse 2\n").unwrap();
+        graph.merge(&other);
+        let node = graph.root.node(parse);
+        assert_eq!((node.total_value, node.self_value), (6, 3));
+
+        graph.recursion = Recursion::Indirect;
+        graph.rederive();
+        let t = graph.root.root().children[0];
+        let a = node_named(&graph, t, "a");
+        let b = node_named(&graph, a, "b");
+        assert_eq!(child_names(&graph, b), ["c"]);
+        let c = node_named(&graph, b, "c");
+        assert_eq!(graph.root.node(a).total_value, 2);
+        assert_eq!(graph.root.node(c).self_value, 2);
+        assert_eq!(graph.root.root().total_value, 8);
+
+        graph.recursion = Recursion::Keep;
+        graph.rederive();
+        let t = graph.root.root().children[0];
+        let parse = node_named(&graph, t, "parse");
+        assert_eq!(child_names(&graph, parse), ["parse"]);
+    }
+
+    #[test]
+    fn budget_prunes_cold_subtrees_into_other() {
+        let mut graph = FlameGraph::from_folded("t;hot;a 90\nt;cold1 3\nt;cold2;x 2\n").unwrap();
+        let mut budget = NodeBudget {
+            max_nodes: 7,
+            min_share: 0.05,
+        };
+        assert_eq!(graph.enforce_budget(&budget), None);
+
+        budget.max_nodes = 6;
+        assert_eq!(graph.enforce_budget(&budget), Some(0.05));
+        graph.root.sort();
+        assert_eq!(graph.node_count(), 5);
+        assert_eq!(graph.root.root().total_value, 95);
+        let t = graph.root.root().children[0];
+        assert_eq!(graph.root.node(t).total_value, 95);
+        assert_eq!(child_names(&graph, t), ["hot", OTHER]);
+        let other = graph.root.node(t).children[1];
+        assert_eq!(graph.root.node(other).self_value, 5);
+        assert!(graph.root.node(other).children.is_empty());
+
+        let more = FlameGraph::from_folded("t;cold1 1\n").unwrap();
+        graph.merge(&more);
+        assert_eq!(graph.enforce_budget(&budget), None);
+        assert_eq!(graph.root.node(t).total_value, 96);
+    }
+
+    #[test]
+    fn budget_prunes_functions_by_their_resolved_name() {
+        let mut graph = FlameGraph::new();
+        let t = Frame::new("t".into());
+        add(&mut graph, &[t.clone(), Frame::new("hot".into())], 80);
+        add(&mut graph, &[t.clone(), Frame::new("foo".into())], 3);
+        add(&mut graph, &[t.clone(), native("libfoo.so", 0x10)], 3);
+        for cold in ["c1", "c2", "c3", "c4"] {
+            let stack = [t.clone(), Frame::new(cold.into()), Frame::new("x".into())];
+            add(&mut graph, &stack, 2);
+        }
+        graph.symbolize(|_| Some(vec!["foo".to_string()]));
+
+        let budget = NodeBudget {
+            max_nodes: 8,
+            min_share: 0.05,
+        };
+        assert_eq!(graph.enforce_budget(&budget), Some(0.05));
+        graph.root.sort();
+        let t = graph.root.root().children[0];
+        assert_eq!(child_names(&graph, t), ["hot", OTHER, "foo"]);
+        let foo = graph.root.node(t).children[2];
+        assert_eq!(graph.root.node(foo).total_value, 6);
+    }
+
+    #[test]
+    fn focus_and_ignore_filter_stacks() {
+        let mut graph = FlameGraph::from_folded(
+            "t;main;tokio::runtime::park;epoll 4\nt;main;work;tokio::runtime::park 2\nt;main;work;compute 3\nu;idle 1\n",
+        )
+        .unwrap();
+        graph.filter.focus = Some(Regex::new("park").unwrap());
+        graph.rederive();
+        graph.root.sort();
+        assert_eq!(graph.root.root().total_value, 6);
+        assert_eq!(child_names(&graph, FlameTree::ROOT), ["t"]);
+        let t = graph.root.root().children[0];
+        let main = graph.root.node(t).children[0];
+        assert_eq!(child_names(&graph, main), ["tokio::runtime::park", "work"]);
+        let work = graph.root.node(main).children[1];
+        assert_eq!(child_names(&graph, work), ["tokio::runtime::park"]);
+
+        graph.filter.reroot = true;
+        graph.rederive();
+        assert_eq!(
+            child_names(&graph, FlameTree::ROOT),
+            ["tokio::runtime::park"]
+        );
+        let park = graph.root.root().children[0];
+        assert_eq!(graph.root.node(park).total_value, 6);
+        assert_eq!(child_names(&graph, park), ["epoll"]);
+
+        let other = FlameGraph::from_folded("v;tokio::runtime::park 1\nv;other 5\n").unwrap();
+        graph.merge(&other);
+        assert_eq!(graph.root.node(park).total_value, 7);
+        assert_eq!(graph.root.root().total_value, 7);
+
+        graph.filter = StackFilter {
+            ignore: Some(Regex::new("^work$").unwrap()),
+            ..StackFilter::default()
+        };
+        graph.rederive();
+        assert_eq!(graph.root.root().total_value, 11);
+        let t = graph
+            .root
+            .child_by_name(FlameTree::ROOT, graph.names.get("t").unwrap());
+        assert_eq!(graph.root.node(t.unwrap()).total_value, 4);
+    }
+
+    #[test]
+    fn inlined_functions_are_separate_nodes() {
+        let mut graph = FlameGraph::new();
+        let thread = Frame::new("t".into());
+        let inlined = Frame {
+            name: "f".into(),
+            inlined: vec!["g".into(), "h".into()],
+            native: None,
+        };
+        add(
+            &mut graph,
+            &[thread.clone(), inlined, Frame::new("leaf".into())],
+            3,
+        );
+        add(&mut graph, &[thread.clone(), native("libfoo.so", 0x10)], 2);
+        graph.symbolize(|_| Some(vec!["a".into(), "b".into()]));
+
+        let t = graph.root.root().children[0];
+        let f = graph
+            .root
+            .child_by_name(t, graph.names.get("f").unwrap())
+            .unwrap();
+        assert!(!graph.root.node(f).inlined);
+        assert_eq!(child_names(&graph, f), ["g"]);
+        let g = graph.root.node(f).children[0];
+        let h = graph.root.node(g).children[0];
+        assert!(graph.root.node(g).inlined && graph.root.node(h).inlined);
+        assert_eq!(child_names(&graph, h), ["leaf"]);
+        assert_eq!(graph.root.node(h).total_value, 3);
+        let a = graph
+            .root
+            .child_by_name(t, graph.names.get("a").unwrap())
+            .unwrap();
+        let b = graph.root.node(a).children[0];
+        assert!(graph.root.node(b).inlined);
+        assert_eq!(graph.root.node(b).self_value, 2);
+
+        // The same call made without inlining clears the mark.
+        let other = FlameGraph::from_folded("t;f;g 1\n").unwrap();
+        graph.merge(&other);
+        assert!(!graph.root.node(g).inlined);
+
+        graph.fold_inlines = true;
+        graph.rederive();
+        let t = graph.root.root().children[0];
+        let f = graph
+            .root
+            .child_by_name(t, graph.names.get("f").unwrap())
+            .unwrap();
+        assert_eq!(child_names(&graph, f), ["leaf", "g"]);
+        assert_eq!(graph.root.node(f).total_value, 4);
+        let a = graph
+            .root
+            .child_by_name(t, graph.names.get("a").unwrap())
+            .unwrap();
+        assert_eq!(graph.root.node(a).self_value, 2);
+        assert!(graph.root.node(a).children.is_empty());
+    }
+}
@@ -2,8 +2,9 @@ use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock, mpsc};
 use tonic::{Request, Response, Status};
 
-use crate::flamegraph::FlameGraph;
-use crate::storage::SymbolStore;
+use crate::flamegraph::{FlameGraph, Frame, NameId, NativeFrame};
+use crate::perfmap::PerfMaps;
+use crate::storage::{FileId, SymbolStore};
 use crate::tui::event::Event;
 use eprofiler_proto::opentelemetry::proto::collector::profiles::v1development as collector;
 use eprofiler_proto::opentelemetry::proto::common::v1 as common;
@@ -12,27 +13,42 @@ use eprofiler_proto::opentelemetry::proto::profiles::v1development as profiles;
 pub struct ProfilesServer {
     event_tx: mpsc::Sender<Event>,
     store: Arc<SymbolStore>,
+    perf_maps: Arc<PerfMaps>,
     known_basenames: Arc<RwLock<HashSet<String>>>,
 }
 
 impl ProfilesServer {
-    pub fn new(event_tx: mpsc::Sender<Event>, store: Arc<SymbolStore>) -> Self {
+    pub fn new(
+        event_tx: mpsc::Sender<Event>,
+        store: Arc<SymbolStore>,
+        perf_maps: Arc<PerfMaps>,
+    ) -> Self {
         Self {
             event_tx,
             store,
+            perf_maps,
             known_basenames: Arc::new(RwLock::new(HashSet::new())),
         }
     }
 }
 
-fn unknown_basenames(
+/// A mapping seen for the first time, as reported by the sender.
+pub struct DiscoveredMapping {
+    pub basename: String,
+    /// Full path of the object inside the profiled process.
+    pub path: String,
+    /// File ID reported via `process.executable.build_id.htlhash`, if any.
+    pub file_id: Option<FileId>,
+}
+
+fn discover_mappings(
     known: &RwLock<HashSet<String>>,
     dict: &profiles::ProfilesDictionary,
-) -> Vec<String> {
+) -> Vec<DiscoveredMapping> {
     dict.mapping_table
         .iter()
         .skip(1)
-        .fold(Vec::new(), |mut names, mapping| {
+        .fold(Vec::new(), |mut found, mapping| {
             let name_idx = mapping.filename_strindex as usize;
             if name_idx != 0 && name_idx < dict.string_table.len() {
                 let full_path = &dict.string_table[name_idx];
@@ -40,54 +56,52 @@ fn unknown_basenames(
                     let basename = full_path.rsplit('/').next().unwrap_or(full_path);
                     if !basename.is_empty() && !basename.starts_with('[') {
                         known.write().unwrap().insert(full_path.to_string());
-                        names.push(basename.to_string());
+                        found.push(DiscoveredMapping {
+                            basename: basename.to_string(),
+                            path: full_path.to_string(),
+                            file_id: resolve_mapping_file_id(mapping, dict),
+                        });
                     }
                 }
             }
-            names
+            found
         })
 }
 
-/// Pre-resolves the location table into human-readable strings.
+/// Pre-resolves the location table into stack frames.
 /// This turns a complex Protobuf traversal into a simple O(1) vector lookup.
-fn pre_resolve_locations(dict: &profiles::ProfilesDictionary, store: &SymbolStore) -> Vec<String> {
+///
+/// Native frames without lines keep their mapping and address so they can be
+/// symbolized (again) whenever symbols for the mapping change.
+fn pre_resolve_locations(dict: &profiles::ProfilesDictionary) -> Vec<Frame> {
     dict.location_table
         .iter()
         .map(|location| {
             let frame_tag = resolve_frame_type(location, dict);
             if location.lines.is_empty() {
-                // Try native symbolication
-                if frame_tag == "Native"
-                    && let Some(names) = symbolize_native(store, location, dict)
-                {
-                    // Join inlined native frames into one string for the cache
-                    return names
-                        .iter()
-                        .enumerate()
-                        .map(|(i, n)| {
-                            format!("{} [Native]{}", n, if i > 0 { " [Inline]" } else { "" })
-                        })
-                        .collect::<Vec<_>>()
-                        .join(" / ");
+                let name = format_with_tag(&resolve_unsymbolized_label(location, dict), &frame_tag);
+                Frame {
+                    name,
+                    inlined: Vec::new(),
+                    native: (frame_tag == "Native").then(|| NativeFrame {
+                        mapping: resolve_mapping_filename(location, dict),
+                        address: location.address,
+                        file_offset: resolve_file_offset(location, dict),
+                        pid: None,
+                    }),
                 }
-                format_with_tag(&resolve_unsymbolized_label(location, dict), &frame_tag)
             } else {
-                // Resolve known lines
-                location
-                    .lines
-                    .iter()
-                    .enumerate()
-                    .map(|(i, line)| {
-                        let func_name = resolve_function_name(line, dict);
-                        format!(
-                            "{} [{}]{}",
-                            func_name,
-                            frame_tag,
-                            if i > 0 { " [Inline]" } else { "" }
-                        )
-                    })
-                    .collect::<Vec<_>>()
-                    .join(" / ")
+                // Lines run from the innermost inlined callee to the caller
+                // they were inlined into, which is the frame itself.
+                let mut names =
+                    location.lines.iter().rev().map(|line| {
+                        format!("{} [{}]", resolve_function_name(line, dict), frame_tag)
+                    });
+                Frame {
+                    name: names.next().unwrap_or_default(),
+                    inlined: names.collect(),
+                    native: None,
+                }
             }
         })
         .collect()
@@ -96,6 +110,7 @@ fn pre_resolve_locations(dict: &profiles::ProfilesDictionary, store: &SymbolStor
 fn process_export(
     req: collector::ExportProfilesServiceRequest,
     store: &SymbolStore,
+    perf_maps: &PerfMaps,
     known: &RwLock<HashSet<String>>,
     event_tx: &mpsc::Sender<Event>,
 ) {
@@ -105,8 +120,8 @@ fn process_export(
         return;
     };
 
-    let mut stack_cache: HashMap<i32, Vec<String>> = HashMap::new();
-    let location_cache = pre_resolve_locations(dict, store);
+    let mut stack_cache: HashMap<(i32, Option<u32>), Vec<NameId>> = HashMap::new();
+    let location_cache = pre_resolve_locations(dict);
 
     let mut sample_count: u64 = 0;
     let mut thread_timestamps: HashMap<String, Vec<u64>> = HashMap::new();
@@ -115,7 +130,9 @@ fn process_export(
         for scope_profiles in &resource_profiles.scope_profiles {
             for profile in &scope_profiles.profiles {
                 for sample in &profile.samples {
-                    let stack = stack_cache.entry(sample.stack_index).or_insert_with(|| {
+                    let pid = resolve_process_id(sample, resource_profiles, dict);
+                    let key = (sample.stack_index, pid);
+                    let stack = stack_cache.entry(key).or_insert_with(|| {
                         let idx = sample.stack_index as usize;
                         if idx == 0 || idx >= dict.stack_table.len() {
                             return Vec::new();
@@ -125,22 +142,28 @@ fn process_export(
                         for &loc_idx in &dict.stack_table[idx].location_indices {
                             let loc_idx = loc_idx as usize;
                             if loc_idx < location_cache.len() {
-                                frames.push(location_cache[loc_idx].clone());
+                                let mut frame = location_cache[loc_idx].clone();
+                                if let Some(native) = &mut frame.native
+                                    && is_anonymous_mapping(&native.mapping)
+                                {
+                                    native.pid = pid;
+                                }
+                                frames.push(frame);
                             }
                         }
                         frames.reverse(); // Standard pprof leaf-to-root reversal
 
                         let comm = resolve_thread_name(sample, dict);
                         let mut result = Vec::with_capacity(frames.len() + 1);
-                        result.push(comm);
-                        result.extend(frames);
+                        result.push(flamegraph.intern_frame(&Frame::new(comm)));
+                        result.extend(frames.iter().map(|f| flamegraph.intern_frame(f)));
                         result
                     });
 
                     if !stack.is_empty() {
                         let value = if !sample.timestamps_unix_nano.is_empty() {
                             thread_timestamps
-                                .entry(stack[0].clone())
+                                .entry(flamegraph.name(stack[0]).to_string())
                                 .or_default()
                                 .extend_from_slice(&sample.timestamps_unix_nano);
                             sample.timestamps_unix_nano.len() as i64
@@ -158,12 +181,14 @@ fn process_export(
         }
     }
 
-    let basenames = unknown_basenames(known, dict);
-    if !basenames.is_empty() {
-        let _ = event_tx.send(Event::MappingsDiscovered(basenames));
+    flamegraph.symbolize(|frame| resolve_native_frame(store, perf_maps, frame));
+
+    let mappings = discover_mappings(known, dict);
+    if !mappings.is_empty() {
+        let _ = event_tx.send(Event::MappingsDiscovered(mappings));
     }
     let _ = event_tx.send(Event::ProfileUpdate {
-        flamegraph,
+        flamegraph: Box::new(flamegraph),
         samples: sample_count,
         timestamps: thread_timestamps,
     });
@@ -177,12 +202,14 @@ impl collector::profiles_service_server::ProfilesService for ProfilesServer {
     ) -> Result<Response<collector::ExportProfilesServiceResponse>, Status> {
         tokio::task::spawn_blocking({
             let store = self.store.clone();
+            let perf_maps = Arc::clone(&self.perf_maps);
             let known_basenames = Arc::clone(&self.known_basenames);
             let event_tx = self.event_tx.clone();
             move || {
                 process_export(
                     request.into_inner(),
                     store.as_ref(),
+                    &perf_maps,
                     &known_basenames,
                     &event_tx,
                 );
@@ -195,22 +222,48 @@ impl collector::profiles_service_server::ProfilesService for ProfilesServer {
     }
 }
 
-/// Try to symbolize a native frame via the local symbol store.
-fn symbolize_native(
+/// Try to symbolize a native frame via perf maps (anonymous mappings) or
+/// the local symbol store.
+///
+/// Inlined callees follow their containing function.
+pub fn resolve_native_frame(
     store: &SymbolStore,
-    location: &profiles::Location,
-    dict: &profiles::ProfilesDictionary,
+    perf_maps: &PerfMaps,
+    frame: &NativeFrame,
 ) -> Option<Vec<String>> {
+    if is_anonymous_mapping(&frame.mapping) {
+        return perf_maps
+            .lookup(frame.pid, frame.address)
+            .map(|name| vec![format!("{} [JIT]", name)]);
+    }
+    let file_id = store.file_id_for_basename(&frame.mapping)?;
     let resolved = store
-        .lookup(
-            store.file_id_for_basename(&resolve_mapping_filename(location, dict))?,
-            location.address,
-        )
+        .lookup(file_id, symbol_address(store, file_id, frame))
         .ok()?;
     if resolved.is_empty() {
         return None;
     }
-    Some(resolved.into_iter().map(|f| f.func).collect())
+    Some(
+        resolved
+            .iter()
+            .map(|f| format!("{} [Native]", f.func))
+            .collect(),
+    )
+}
+
+/// Whether perf maps name `frame`, a frame of an anonymous mapping.
+pub fn resolves_with_perf_maps(perf_maps: &PerfMaps, frame: &NativeFrame) -> bool {
+    is_anonymous_mapping(&frame.mapping) && perf_maps.lookup(frame.pid, frame.address).is_some()
+}
+
+/// Address `frame` is looked up at: its file offset translated through the
+/// file's load segments, or the address as sent for files stored without
+/// segments (imported before they were recorded, or not inspectable).
+fn symbol_address(store: &SymbolStore, file_id: FileId, frame: &NativeFrame) -> u64 {
+    frame
+        .file_offset
+        .and_then(|offset| store.elf_address(file_id, offset))
+        .unwrap_or(frame.address)
 }
 
 fn resolve_function_name(line: &profiles::Line, dict: &profiles::ProfilesDictionary) -> String {
@@ -257,6 +310,56 @@ fn resolve_mapping_filename(
     }
 }
 
+/// File offset of a location whose address is a process virtual address,
+/// i.e. falls inside its mapping's `[memory_start, memory_limit)`.
+///
+/// The eBPF profiler already reports ELF addresses, which normally lie
+/// outside that range; for non-PIE executables, where both coincide, the
+/// translation is the identity.
+fn resolve_file_offset(
+    location: &profiles::Location,
+    dict: &profiles::ProfilesDictionary,
+) -> Option<u64> {
+    let mapping_idx = location.mapping_index as usize;
+    if mapping_idx == 0 {
+        return None;
+    }
+    let mapping = dict.mapping_table.get(mapping_idx)?;
+    (mapping.memory_start..mapping.memory_limit)
+        .contains(&location.address)
+        .then(|| location.address - mapping.memory_start + mapping.file_offset)
+}
+
+/// Mappings without a backing file, where JIT code lives.
+fn is_anonymous_mapping(basename: &str) -> bool {
+    basename.starts_with('[') || basename.starts_with("memfd:") || basename == "//anon"
+}
+
+fn resolve_mapping_file_id(
+    mapping: &profiles::Mapping,
+    dict: &profiles::ProfilesDictionary,
+) -> Option<FileId> {
+    for &attr_idx in &mapping.attribute_indices {
+        let attr_idx = attr_idx as usize;
+        if attr_idx == 0 || attr_idx >= dict.attribute_table.len() {
+            continue;
+        }
+        let attr = &dict.attribute_table[attr_idx];
+        let key_idx = attr.key_strindex as usize;
+        if key_idx >= dict.string_table.len()
+            || dict.string_table[key_idx] != "process.executable.build_id.htlhash"
+        {
+            continue;
+        }
+        if let Some(ref value) = attr.value
+            && let Some(common::any_value::Value::StringValue(ref s)) = value.value
+        {
+            return u128::from_str_radix(s, 16).ok().map(FileId::from);
+        }
+    }
+    None
+}
+
 fn resolve_frame_type(
     location: &profiles::Location,
     dict: &profiles::ProfilesDictionary,
@@ -304,6 +407,43 @@ fn format_with_tag(label: &str, tag: &str) -> String {
     }
 }
 
+/// `process.pid` from the sample attributes, falling back to the resource.
+fn resolve_process_id(
+    sample: &profiles::Sample,
+    resource_profiles: &profiles::ResourceProfiles,
+    dict: &profiles::ProfilesDictionary,
+) -> Option<u32> {
+    let as_pid = |value: &Option<common::AnyValue>| match value.as_ref()?.value {
+        Some(common::any_value::Value::IntValue(pid)) => u32::try_from(pid).ok(),
+        Some(common::any_value::Value::StringValue(ref s)) => s.parse().ok(),
+        _ => None,
+    };
+    for &attr_idx in &sample.attribute_indices {
+        let attr_idx = attr_idx as usize;
+        if attr_idx == 0 || attr_idx >= dict.attribute_table.len() {
+            continue;
+        }
+        let attr = &dict.attribute_table[attr_idx];
+        let key_idx = attr.key_strindex as usize;
+        if key_idx < dict.string_table.len() && dict.string_table[key_idx] == "process.pid" {
+            return as_pid(&attr.value);
+        }
+    }
+    resource_profiles
+        .resource
+        .as_ref()?
+        .attributes
+        .iter()
+        .find(|kv| {
+            let key_idx = kv.key_strindex as usize;
+            kv.key == "process.pid"
+                || (key_idx != 0
+                    && key_idx < dict.string_table.len()
+                    && dict.string_table[key_idx] == "process.pid")
+        })
+        .and_then(|kv| as_pid(&kv.value))
+}
+
 fn resolve_thread_name(sample: &profiles::Sample, dict: &profiles::ProfilesDictionary) -> String {
     for &attr_idx in &sample.attribute_indices {
         let attr_idx = attr_idx as usize;
@@ -331,9 +471,10 @@ pub async fn start_server(
     event_tx: mpsc::Sender<Event>,
     addr: &str,
     store: Arc<SymbolStore>,
+    perf_maps: Arc<PerfMaps>,
 ) -> Result<(), tonic::transport::Error> {
     let addr = addr.parse().expect("invalid gRPC listen address");
-    let server = ProfilesServer::new(event_tx, store);
+    let server = ProfilesServer::new(event_tx, store, perf_maps);
 
     tonic::transport::Server::builder()
         .add_service(
@@ -355,10 +496,108 @@ mod tests {
     use common::AnyValue;
     use common::any_value;
     use profiles::{
-        Function, KeyValueAndUnit, Line, Location, Profile, ProfilesDictionary, ResourceProfiles,
-        Sample, ScopeProfiles, Stack,
+        Function, KeyValueAndUnit, Line, Location, Mapping, Profile, ProfilesDictionary,
+        ResourceProfiles, Sample, ScopeProfiles, Stack,
     };
 
+    #[test]
+    fn file_offsets_fall_back_to_addresses_without_segments() {
+        let dict = ProfilesDictionary {
+            mapping_table: vec![
+                Mapping::default(),
+                Mapping {
+                    memory_start: 0x5000,
+                    memory_limit: 0x6000,
+                    file_offset: 0x1000,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let location = |address| Location {
+            mapping_index: 1,
+            address,
+            ..Default::default()
+        };
+        assert_eq!(resolve_file_offset(&location(0x5010), &dict), Some(0x1010));
+        assert_eq!(resolve_file_offset(&location(0x7000), &dict), None);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let store = crate::storage::SymbolStore::open(tmp.path()).unwrap();
+        let frame = NativeFrame {
+            mapping: "app".to_string(),
+            address: 0x5010,
+            file_offset: Some(0x1010),
+            pid: None,
+        };
+        assert_eq!(symbol_address(&store, FileId::from(1), &frame), 0x5010);
+    }
+
+    #[test]
+    fn inlined_lines_nest_under_their_caller() {
+        let mut dict = build_dictionary();
+        dict.string_table.extend(["printf".into(), "memcpy".into()]);
+        dict.function_table.extend([
+            Function {
+                name_strindex: 5,
+                ..Default::default()
+            },
+            Function {
+                name_strindex: 6,
+                ..Default::default()
+            },
+        ]);
+        // `memcpy` inlined into `printf`, called from `main`.
+        let line = |function_index| Line {
+            function_index,
+            ..Default::default()
+        };
+        dict.location_table.push(Location {
+            lines: vec![line(4), line(3)],
+            ..Default::default()
+        });
+        dict.stack_table.push(Stack {
+            location_indices: vec![3, 2],
+        });
+        let req = ExportProfilesServiceRequest {
+            dictionary: Some(dict),
+            resource_profiles: vec![ResourceProfiles {
+                scope_profiles: vec![ScopeProfiles {
+                    profiles: vec![Profile {
+                        samples: vec![Sample {
+                            stack_index: 2,
+                            values: vec![1],
+                            attribute_indices: vec![1],
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+
+        let tmp = tempfile::tempdir().unwrap();
+        let store = crate::storage::SymbolStore::open(tmp.path()).unwrap();
+        let (tx, rx) = mpsc::channel();
+        let known = RwLock::new(HashSet::new());
+        process_export(req, &store, &PerfMaps::default(), &known, &tx);
+
+        let Ok(Event::ProfileUpdate { flamegraph, .. }) = rx.try_recv() else {
+            panic!("expected ProfileUpdate event");
+        };
+        let tree = &flamegraph.root;
+        let main = tree.node(tree.node(tree.root().children[0]).children[0]);
+        assert_eq!(flamegraph.name(main.name), "main [Unknown]");
+        let printf = tree.node(main.children[0]);
+        assert_eq!(flamegraph.name(printf.name), "printf [Unknown]");
+        assert!(!printf.inlined);
+        let memcpy = tree.node(printf.children[0]);
+        assert_eq!(flamegraph.name(memcpy.name), "memcpy [Unknown]");
+        assert!(memcpy.inlined);
+    }
+
     async fn setup_server(tx: mpsc::Sender<Event>) -> u16 {
         let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
         let port = listener.local_addr().unwrap().port();
@@ -366,7 +605,7 @@ mod tests {
         let store = Arc::new(crate::storage::SymbolStore::open(tmp.path()).unwrap());
         tokio::spawn(async move {
             let _tmp = tmp; // keep tempdir alive for the server's lifetime
-            let server = ProfilesServer::new(tx, store);
+            let server = ProfilesServer::new(tx, store, Arc::new(PerfMaps::default()));
             tonic::transport::Server::builder()
                 .add_service(collector::profiles_service_server::ProfilesServiceServer::new(server))
                 .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
@@ -474,11 +713,14 @@ mod tests {
             } => {
                 assert_eq!(samples, 10);
                 assert!(timestamps.is_empty());
-                let thread = &flamegraph.root.children[0];
-                assert_eq!(thread.name, "worker-1");
+                let tree = &flamegraph.root;
+                let thread = tree.node(tree.root().children[0]);
+                assert_eq!(flamegraph.name(thread.name), "worker-1");
                 assert_eq!(thread.total_value, 10);
-                assert_eq!(thread.children[0].name, "main [Unknown]");
-                assert_eq!(thread.children[0].children[0].name, "do_work [Unknown]");
+                let main = tree.node(thread.children[0]);
+                assert_eq!(flamegraph.name(main.name), "main [Unknown]");
+                let do_work = tree.node(main.children[0]);
+                assert_eq!(flamegraph.name(do_work.name), "do_work [Unknown]");
             }
             _ => panic!("expected ProfileUpdate event"),
         }
@@ -528,8 +770,8 @@ mod tests {
                     timestamps.get("worker-1").unwrap(),
                     &vec![100, 200, 300, 400, 500]
                 );
-                let thread = &flamegraph.root.children[0];
-                assert_eq!(thread.total_value, 5);
+                let tree = &flamegraph.root;
+                assert_eq!(tree.node(tree.root().children[0]).total_value, 5);
             }
             _ => panic!("expected ProfileUpdate event"),
         }
@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, mpsc};
+
+use crate::tui::event::Event;
+
+pub type JobId = u64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Parsing,
+    Extracting,
+    Storing,
+}
+
+#[derive(Clone, Debug)]
+pub enum JobState {
+    Queued,
+    Running { phase: Phase, ranges: usize },
+    Done { ranges: u32 },
+    Failed(String),
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Done { .. } | Self::Failed(_) | Self::Cancelled)
+    }
+}
+
+/// A symbol load waiting for, or running on, a worker.
+pub struct LoadJob {
+    pub id: JobId,
+    pub path: PathBuf,
+    pub target_name: Option<String>,
+    /// Canonical path, identifying loads of the same file.
+    key: PathBuf,
+    cancel: Arc<AtomicBool>,
+}
+
+impl LoadJob {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    jobs: VecDeque<LoadJob>,
+    /// Files being loaded, with the job loading them.
+    running: HashMap<PathBuf, (JobId, Arc<AtomicBool>)>,
+}
+
+impl Queue {
+    /// A live (not cancelled) job for the file behind `key`.
+    fn live(&self, key: &Path) -> Option<JobId> {
+        let queued = self
+            .jobs
+            .iter()
+            .find(|job| job.key == key && !job.is_cancelled())
+            .map(|job| job.id);
+        let running = self
+            .running
+            .get(key)
+            .filter(|(_, cancel)| !cancel.load(Ordering::Relaxed))
+            .map(|(id, _)| *id);
+        queued.or(running)
+    }
+
+    /// Take the first job whose file is not being loaded already.
+    fn next(&mut self) -> Option<LoadJob> {
+        let busy: HashSet<&PathBuf> = self.running.keys().collect();
+        let idx = self.jobs.iter().position(|job| !busy.contains(&job.key))?;
+        let job = self.jobs.remove(idx)?;
+        self.running
+            .insert(job.key.clone(), (job.id, Arc::clone(&job.cancel)));
+        Some(job)
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+    cancels: Mutex<HashMap<JobId, Arc<AtomicBool>>>,
+}
+
+/// Symbol loads run on a fixed pool of worker threads.
+///
+/// Submitting reports [`Event::JobQueued`]; the `run` callback given to
+/// [`JobQueue::new`] reports everything after that, and is expected to
+/// honour [`LoadJob::is_cancelled`]. Loads of the same file never run
+/// concurrently, and submitting one that is already queued or running
+/// returns the existing job.
+pub struct JobQueue {
+    shared: Arc<Shared>,
+    next_id: AtomicU64,
+    sender: mpsc::Sender<Event>,
+}
+
+impl JobQueue {
+    pub fn new(
+        workers: usize,
+        sender: mpsc::Sender<Event>,
+        run: impl Fn(&LoadJob) + Send + Sync + 'static,
+    ) -> Self {
+        let shared = Arc::new(Shared::default());
+        let run = Arc::new(run);
+        for _ in 0..workers.max(1) {
+            std::thread::spawn({
+                let shared = Arc::clone(&shared);
+                let run = Arc::clone(&run);
+                move || {
+                    loop {
+                        let job = {
+                            let mut queue = shared.queue.lock().unwrap();
+                            loop {
+                                match queue.next() {
+                                    Some(job) => break job,
+                                    None => queue = shared.ready.wait(queue).unwrap(),
+                                }
+                            }
+                        };
+                        run(&job);
+                        shared.cancels.lock().unwrap().remove(&job.id);
+                        shared.queue.lock().unwrap().running.remove(&job.key);
+                        // A load of the same file may have been waiting.
+                        shared.ready.notify_all();
+                    }
+                }
+            });
+        }
+        Self {
+            shared,
+            next_id: AtomicU64::new(1),
+            sender,
+        }
+    }
+
+    pub fn submit(&self, path: PathBuf, target_name: Option<String>) -> JobId {
+        let key = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        let mut queue = self.shared.queue.lock().unwrap();
+        if let Some(id) = queue.live(&key) {
+            return id;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let name = target_name.clone().unwrap_or_else(|| {
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string())
+        });
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.shared
+            .cancels
+            .lock()
+            .unwrap()
+            .insert(id, Arc::clone(&cancel));
+        let _ = self.sender.send(Event::JobQueued { id, name });
+        queue.jobs.push_back(LoadJob {
+            id,
+            path,
+            target_name,
+            key,
+            cancel,
+        });
+        drop(queue);
+        self.shared.ready.notify_one();
+        id
+    }
+
+    /// Request cancellation; queued jobs are dropped when a worker picks
+    /// them up, running ones stop at their next progress check.
+    pub fn cancel(&self, id: JobId) {
+        if let Some(cancel) = self.shared.cancels.lock().unwrap().get(&id) {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn cancelled_jobs_are_flagged_before_running() {
+        let (events, _rx) = mpsc::channel();
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        let gate_rx = Mutex::new(gate_rx);
+        let (done_tx, done_rx) = mpsc::channel();
+        let done_tx = Mutex::new(done_tx);
+        let queue = JobQueue::new(1, events, move |job| {
+            // Hold the only worker until the test releases it.
+            if job.id == 1 {
+                gate_rx.lock().unwrap().recv().unwrap();
+            }
+            let _ = done_tx.lock().unwrap().send((job.id, job.is_cancelled()));
+        });
+
+        let first = queue.submit(PathBuf::from("/bin/first"), None);
+        let second = queue.submit(PathBuf::from("/bin/second"), None);
+        queue.cancel(second);
+        gate_tx.send(()).unwrap();
+
+        let timeout = Duration::from_secs(2);
+        assert_eq!(done_rx.recv_timeout(timeout).unwrap(), (first, false));
+        assert_eq!(done_rx.recv_timeout(timeout).unwrap(), (second, true));
+    }
+
+    #[test]
+    fn loads_of_the_same_file_share_a_job() {
+        let (events, _rx) = mpsc::channel();
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        let gate_rx = Mutex::new(gate_rx);
+        let (done_tx, done_rx) = mpsc::channel();
+        let done_tx = Mutex::new(done_tx);
+        let queue = JobQueue::new(2, events, move |job| {
+            if job.id == 1 {
+                gate_rx.lock().unwrap().recv().unwrap();
+            }
+            let _ = done_tx.lock().unwrap().send((job.id, job.is_cancelled()));
+        });
+
+        let first = queue.submit(PathBuf::from("/bin/app"), None);
+        assert_eq!(queue.submit(PathBuf::from("/bin/app"), None), first);
+
+        // A cancelled load can be retried, but only runs after it.
+        queue.cancel(first);
+        let retry = queue.submit(PathBuf::from("/bin/app"), None);
+        assert_ne!(retry, first);
+        gate_tx.send(()).unwrap();
+
+        let timeout = Duration::from_secs(2);
+        assert_eq!(done_rx.recv_timeout(timeout).unwrap(), (first, true));
+        assert_eq!(done_rx.recv_timeout(timeout).unwrap(), (retry, false));
+    }
+}
@@ -1,27 +1,51 @@
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
 
 use clap::{Parser, Subcommand};
 use directories::ProjectDirs;
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 
+mod autoload;
+mod cache;
 mod debug;
+mod debugfile;
 mod error;
 mod flamegraph;
 mod grpc;
+mod jobs;
+mod perfmap;
+mod procmaps;
+mod rules;
+mod stale;
 mod storage;
 mod symbolizer;
 mod tui;
 
-use error::Result;
-use storage::SymbolStore;
+use autoload::{PathRewrite, SymbolSearch};
+use error::{Error, Result};
+use flamegraph::{FlameGraph, NodeBudget, ThreadRule, ThreadView};
+use jobs::{JobQueue, JobState, LoadJob, Phase};
+use perfmap::PerfMaps;
+use rules::FrameRules;
+use storage::{FileId, LOOKUP_FLUSH_INTERVAL, SymbolStore};
 use tui::Tui;
 use tui::event::{Event, EventHandler};
-use tui::state::State;
+use tui::state::{State, WindowSpan};
 
 use crate::tui::state::Action;
 
+/// How often perf map files are checked for newly appended symbols.
+const PERF_MAP_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Minimum time between re-symbolizations for new perf map symbols.
+const PERF_MAP_RESYMBOLIZE_INTERVAL: Duration = Duration::from_secs(5);
+/// Minimum time between progress events of a running symbol load.
+const JOB_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+/// How often source paths of stored files are checked for changes.
+const STALE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 #[derive(Parser)]
 #[command(
     name = "eprofiler-tui",
@@ -34,6 +58,62 @@ struct Cli {
     /// typically ~/.local/share/eprofiler-tui on Linux)
     #[arg(short = 'd', long = "data-dir", value_name = "PATH")]
     data_dir: Option<PathBuf>,
+    /// Maximum symbol store size (e.g. `512M`, `4G`); least recently used
+    /// executables are evicted when a new load exceeds it
+    #[arg(long = "store-quota", value_name = "SIZE", value_parser = parse_size)]
+    store_quota: Option<u64>,
+    /// Directory searched for local copies of discovered mappings, both as a
+    /// sysroot and by basename; matches are loaded automatically (repeatable)
+    #[arg(long = "symbol-path", value_name = "DIR")]
+    symbol_paths: Vec<PathBuf>,
+    /// Rewrite a mapping path prefix before searching, e.g.
+    /// `/app/=/var/lib/containers/.../rootfs/app/` (repeatable)
+    #[arg(long = "path-rewrite", value_name = "FROM=TO")]
+    path_rewrites: Vec<PathRewrite>,
+    /// Directory holding separate debug files, looked up by build ID
+    /// (`.build-id/xx/yyyy.debug`) and `.gnu_debuglink` name (repeatable)
+    #[arg(long = "debug-dir", value_name = "DIR", default_value = debugfile::DEFAULT_DEBUG_DIR)]
+    debug_dirs: Vec<PathBuf>,
+    /// Perf map (`perf-<pid>.map`) or jitdump (`jit-<pid>.dump`) file, or a
+    /// directory of them, used to name JIT frames; re-read as they grow
+    /// (repeatable)
+    #[arg(long = "perf-map", value_name = "PATH")]
+    perf_maps: Vec<PathBuf>,
+    /// Number of symbol loads extracted concurrently
+    #[arg(long = "load-jobs", value_name = "N", default_value_t = 2)]
+    load_jobs: usize,
+    /// Re-extract symbols automatically when a loaded binary changes on
+    /// disk (otherwise it is only flagged as stale)
+    #[arg(long = "reload-stale")]
+    reload_stale: bool,
+    /// Folded-stack file (as exported with `E`) to diff the live graph
+    /// against
+    #[arg(long = "baseline", value_name = "FILE")]
+    baseline: Option<PathBuf>,
+    /// Thread-name normalization rule, `PATTERN=REPLACEMENT` (regex), e.g.
+    /// `-\d+$=-*` to merge `worker-1` … `worker-64` into `worker-*`
+    /// (repeatable)
+    #[arg(long = "thread-rule", value_name = "PATTERN=REPLACEMENT")]
+    thread_rules: Vec<ThreadRule>,
+    /// File of frame hiding, folding and renaming rules (`hide PATTERN`,
+    /// `fold PATTERN`, `rename PATTERN=REPLACEMENT`, one per line)
+    #[arg(long = "rules", value_name = "FILE")]
+    rules: Option<PathBuf>,
+    /// Only show data received in the last DURATION (e.g. `30s`, `5m`,
+    /// `1h`) instead of the whole session
+    #[arg(long = "window", value_name = "DURATION", value_parser = parse_duration, conflicts_with = "window_exports")]
+    window: Option<Duration>,
+    /// Only show the last N received profile exports
+    #[arg(long = "window-exports", value_name = "N")]
+    window_exports: Option<usize>,
+    /// Flamegraph nodes kept before cold subtrees are merged into
+    /// `[other]` nodes (0 for no limit)
+    #[arg(long = "max-nodes", value_name = "N", default_value_t = NodeBudget::DEFAULT_MAX_NODES)]
+    max_nodes: usize,
+    /// Subtrees below this percentage of all samples are pruned first when
+    /// `--max-nodes` is exceeded
+    #[arg(long = "prune-share", value_name = "PERCENT", default_value_t = NodeBudget::DEFAULT_MIN_SHARE * 100.0, value_parser = parse_percent)]
+    prune_share: f64,
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -48,6 +128,160 @@ enum Commands {
     },
 }
 
+fn parse_size(s: &str) -> std::result::Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let value: u64 = digits.parse().map_err(|_| format!("invalid size `{s}`"))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        other => return Err(format!("unknown size unit `{other}`")),
+    };
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size `{s}` is too large"))
+}
+
+fn parse_percent(s: &str) -> std::result::Result<f64, String> {
+    let s = s.trim();
+    let value: f64 = s
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("invalid percentage `{s}`"))?;
+    if value > 0.0 && value <= 100.0 {
+        Ok(value)
+    } else {
+        Err("percentage must be above 0 and at most 100".to_string())
+    }
+}
+
+fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid duration `{s}`"))?;
+    let secs = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        other => return Err(format!("unknown duration unit `{other}`")),
+    };
+    match value.checked_mul(secs) {
+        Some(0) => Err("duration must be positive".to_string()),
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => Err(format!("duration `{s}` is too large")),
+    }
+}
+
+/// Extract symbols for a queued job, committing them to the store chunk by
+/// chunk, then enforce the store quota.
+///
+/// A new file stays invisible to lookups until the last chunk is committed;
+/// a reloaded one keeps its previous symbols until then. Failed or cancelled
+/// imports are discarded; an import cut short by a crash keeps its pending
+/// marker and is resumed on the next start.
+///
+/// Runs on a job queue worker; progress and results are reported through
+/// `sender`.
+fn run_load_job(
+    store: &SymbolStore,
+    sender: &mpsc::Sender<Event>,
+    debug_dirs: &[PathBuf],
+    job: &LoadJob,
+) {
+    let report = |state| {
+        let _ = sender.send(Event::JobUpdate { id: job.id, state });
+    };
+    if job.is_cancelled() {
+        return report(JobState::Cancelled);
+    }
+
+    let path = job.path.as_path();
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    report(JobState::Running {
+        phase: Phase::Parsing,
+        ranges: 0,
+    });
+    let import = FileId::from_path(path)
+        .map_err(Error::from)
+        .and_then(|file_id| Ok((file_id, store.begin_import(file_id, path)?)));
+    let info = import.and_then(|(file_id, mut import)| {
+        let mut ranges = 0;
+        let last_report = Cell::new(Instant::now());
+        // Each report names the phase going on at that moment; both
+        // callbacks share the interval, so reports stay spaced out.
+        let report_phase = |phase, ranges| {
+            if last_report.get().elapsed() >= JOB_PROGRESS_INTERVAL {
+                last_report.set(Instant::now());
+                report(JobState::Running { phase, ranges });
+            }
+        };
+        let extracted = symbolizer::extract_symbols(
+            path,
+            debug_dirs,
+            &mut |extracted| {
+                report_phase(Phase::Extracting, extracted);
+                !job.is_cancelled()
+            },
+            &mut |chunk| {
+                ranges += chunk.ranges.len();
+                report_phase(Phase::Storing, ranges);
+                import.write_chunk(&chunk)
+            },
+        );
+        match extracted {
+            Ok(source) => import.finish(&source),
+            Err(err) => {
+                let _ = store.discard_import(file_id);
+                Err(err)
+            }
+        }
+    });
+
+    let done = match &info {
+        Ok(info) => JobState::Done {
+            ranges: info.num_ranges,
+        },
+        Err(Error::Cancelled) => return report(JobState::Cancelled),
+        Err(err) => JobState::Failed(err.to_string()),
+    };
+    report(done);
+    // A new version of a stale file replaces it.
+    let replaced = match &info {
+        Ok(info) => store
+            .take_stale(path)
+            .filter(|&old| old != info.file_id)
+            .map(|old| store.remove_file_symbols(old)),
+        Err(_) => None,
+    };
+    let evicted = match &info {
+        Ok(info) => store.enforce_quota(info.file_id),
+        Err(_) => Ok(Vec::new()),
+    };
+    let _ = sender.send(Event::SymbolsLoaded {
+        target_name: job.target_name.clone().unwrap_or(file_name.clone()),
+        info,
+    });
+    if let Some(Err(error)) = replaced {
+        let _ = sender.send(Event::SymbolsRemoved {
+            name: file_name,
+            error: Some(error),
+        });
+    }
+    let _ = sender.send(Event::SymbolsEvicted(evicted));
+    let _ = sender.send(Event::StoreUsage(store.usage()));
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
@@ -72,59 +306,169 @@ fn main() -> Result<()> {
             .expect("Failed to create the storage directory. Check permissions.");
     }
 
-    let store = Arc::new(SymbolStore::open(storage_path)?);
+    let store = Arc::new(SymbolStore::open(storage_path)?.with_quota(cli.store_quota));
+    let search = Arc::new(SymbolSearch::new(cli.symbol_paths, cli.path_rewrites));
+    let debug_dirs = cli.debug_dirs;
+    let reload_stale = cli.reload_stale;
+    let rules = cli.rules.as_deref().map(FrameRules::load).transpose()?;
+    let baseline = match &cli.baseline {
+        Some(path) => Some(FlameGraph::from_folded(&std::fs::read_to_string(path)?)?),
+        None => None,
+    };
+    let perf_maps = Arc::new(PerfMaps::new(cli.perf_maps));
+    let mut perf_maps_resymbolized = Instant::now();
+    // New perf map symbols name frames shown unresolved.
+    let mut perf_maps_pending = false;
+    // Symbol events batch into one resymbolize per tick.
+    let mut symbols_changed = false;
     let events = EventHandler::new(100);
+    let jobs = Arc::new(JobQueue::new(cli.load_jobs, events.sender.clone(), {
+        let store = Arc::clone(&store);
+        let sender = events.sender.clone();
+        move |job| run_load_job(&store, &sender, &debug_dirs, job)
+    }));
+
+    stale::watch(
+        Arc::clone(&store),
+        events.sender.clone(),
+        STALE_POLL_INTERVAL,
+    );
+    if perf_maps.is_enabled() {
+        perfmap::watch(
+            Arc::clone(&perf_maps),
+            events.sender.clone(),
+            PERF_MAP_REFRESH_INTERVAL,
+        );
+    }
+    // Resume imports interrupted by a crash, unless the file changed since.
+    for (file_id, path) in store.pending_imports()? {
+        if FileId::from_path(&path).is_ok_and(|id| id == file_id) {
+            jobs.submit(path, None);
+        } else {
+            store.discard_import(file_id)?;
+        }
+    }
 
     std::thread::spawn({
         let store = Arc::clone(&store);
+        let perf_maps = Arc::clone(&perf_maps);
         let listen_addr = listen_addr.clone();
         let events = events.sender.clone();
         move || {
             let rt = tokio::runtime::Runtime::new().expect("failed to create tokio runtime");
             rt.block_on(async {
-                if let Err(e) = grpc::start_server(events, &listen_addr, store).await {
+                if let Err(e) = grpc::start_server(events, &listen_addr, store, perf_maps).await {
                     eprintln!("gRPC server error: {e}");
                 }
             });
         }
     });
 
+    std::thread::spawn({
+        let store = Arc::clone(&store);
+        move || {
+            loop {
+                std::thread::sleep(LOOKUP_FLUSH_INTERVAL);
+                let _ = store.flush_lookups();
+            }
+        }
+    });
+
     let backend = CrosstermBackend::new(std::io::stderr());
     let terminal = Terminal::new(backend)?;
     let mut tui = Tui::new(terminal, events);
     tui.init()?;
 
     let mut state = State::new(listen_addr, store.list_files()?);
+    std::thread::spawn({
+        let sender = tui.events.sender.clone();
+        let store = Arc::clone(&store);
+        move || {
+            let _ = sender.send(Event::StoreUsage(store.usage()));
+        }
+    });
+    if let Some(baseline) = baseline {
+        state.fg.set_baseline(baseline);
+    }
+    let window = match (cli.window, cli.window_exports) {
+        (Some(span), _) => Some(WindowSpan::Duration(span)),
+        (None, Some(n)) => Some(WindowSpan::Exports(n.max(1))),
+        (None, None) => None,
+    };
+    state.fg.set_window(window);
+    state.fg.budget = NodeBudget {
+        max_nodes: cli.max_nodes,
+        min_share: cli.prune_share / 100.0,
+    };
+    state.fg.rules_path = cli.rules;
+    if let Some(rules) = rules {
+        state.fg.set_rules(rules);
+    }
+    if !cli.thread_rules.is_empty() {
+        state.fg.set_thread_view(ThreadView {
+            rules: cli.thread_rules,
+            normalize: true,
+            collapse: false,
+        });
+    }
 
     while state.running {
         tui.draw(&mut state)?;
 
         match tui.events.next()? {
-            Event::Tick => {}
+            Event::Tick => {
+                state.fg.expire_window();
+                if perf_maps_pending
+                    && perf_maps_resymbolized.elapsed() >= PERF_MAP_RESYMBOLIZE_INTERVAL
+                {
+                    perf_maps_pending = false;
+                    perf_maps_resymbolized = Instant::now();
+                    symbols_changed = true;
+                }
+                if symbols_changed {
+                    symbols_changed = false;
+                    state
+                        .fg
+                        .resymbolize(|frame| grpc::resolve_native_frame(&store, &perf_maps, frame));
+                }
+            }
             Event::Key(key_event) => match state.handle_key(key_event) {
                 Action::None => {}
                 Action::LoadSymbols(path, target_name) => {
+                    jobs.submit(path, target_name);
+                }
+                Action::CancelJob(id) => jobs.cancel(id),
+                Action::LoadProcess(pid) => {
                     std::thread::spawn({
                         let store = Arc::clone(&store);
                         let sender = tui.events.sender.clone();
+                        let jobs = Arc::clone(&jobs);
                         move || {
-                            let file_name = path
-                                .file_name()
-                                .map(|n| n.to_string_lossy().into_owned())
-                                .unwrap_or_else(|| path.display().to_string());
-
-                            let _ = sender.send(Event::SymbolsLoaded {
-                                target_name: target_name.unwrap_or(file_name.clone()),
-                                info: symbolizer::extract_symbols(&path).and_then(|file_sym| {
-                                    let info = storage::ExecutableInfo {
-                                        file_id: file_sym.file_id,
-                                        file_name,
-                                        num_ranges: file_sym.ranges.len() as u32,
-                                    };
-                                    store.store_file_symbols(&file_sym, &path)?;
-                                    Ok(info)
-                                }),
+                            let mappings = match procmaps::executable_mappings(pid) {
+                                Ok(mappings) => mappings,
+                                Err(err) => {
+                                    let _ = sender.send(Event::ProcessScanned {
+                                        pid,
+                                        result: Err(err.into()),
+                                    });
+                                    return;
+                                }
+                            };
+                            let (pending, stored): (Vec<_>, Vec<_>) =
+                                mappings.into_iter().partition(|m| {
+                                    !FileId::from_path(&m.host_path)
+                                        .is_ok_and(|file_id| store.contains(file_id))
+                                });
+                            let _ = sender.send(Event::ProcessScanned {
+                                pid,
+                                result: Ok((pending.len(), stored.len())),
                             });
+                            for mapping in pending {
+                                let basename = Path::new(&mapping.path)
+                                    .file_name()
+                                    .map(|n| n.to_string_lossy().into_owned());
+                                jobs.submit(mapping.host_path, basename);
+                            }
                         }
                     });
                 }
@@ -134,10 +478,33 @@ fn main() -> Result<()> {
                         let sender = tui.events.sender.clone();
                         let store = Arc::clone(&store);
                         move || {
-                            Box::new(sender.send(Event::SymbolsRemoved {
-                                name,
-                                error: store.remove_file_symbols(file_id).err(),
-                            }))
+                            let error = store
+                                .remove_file_symbols(file_id)
+                                .and_then(|()| store.compact())
+                                .err();
+                            let _ = sender.send(Event::SymbolsRemoved { name, error });
+                            let _ = sender.send(Event::StoreUsage(store.usage()));
+                        }
+                    });
+                }
+                Action::CompactStore => {
+                    state.exe.status = Some("Compacting symbol store".to_string());
+                    std::thread::spawn({
+                        let sender = tui.events.sender.clone();
+                        let store = Arc::clone(&store);
+                        move || {
+                            let _ = sender.send(Event::StoreCompacted(store.compact().err()));
+                            let _ = sender.send(Event::StoreUsage(store.usage()));
+                        }
+                    });
+                }
+                Action::RefreshStore => {
+                    std::thread::spawn({
+                        let sender = tui.events.sender.clone();
+                        let store = Arc::clone(&store);
+                        move || {
+                            let _ = sender.send(Event::StoreListed(store.list_files()));
+                            let _ = sender.send(Event::StoreUsage(store.usage()));
                         }
                     });
                 }
@@ -151,10 +518,40 @@ fn main() -> Result<()> {
                 if !state.fg.frozen {
                     state.fs.record_timestamps(&timestamps);
                 }
-                state.fg.merge(flamegraph, samples);
+                state.fg.merge(*flamegraph, samples);
             }
-            Event::MappingsDiscovered(names) => {
-                state.exe.merge_discovered_mappings(names);
+            Event::MappingsDiscovered(mappings) => {
+                state.exe.merge_discovered_mappings(
+                    mappings.iter().map(|m| m.basename.clone()).collect(),
+                );
+                if search.is_enabled() {
+                    std::thread::spawn({
+                        let store = Arc::clone(&store);
+                        let jobs = Arc::clone(&jobs);
+                        let search = Arc::clone(&search);
+                        move || {
+                            for mapping in mappings {
+                                if let Some((path, file_id)) = search.locate(&mapping)
+                                    && !store.contains(file_id)
+                                {
+                                    jobs.submit(path, Some(mapping.basename));
+                                }
+                            }
+                        }
+                    });
+                }
+            }
+            Event::JobQueued { id, name } => state.exe.jobs.queued(id, name),
+            Event::JobUpdate {
+                id,
+                state: job_state,
+            } => {
+                if let JobState::Cancelled = job_state
+                    && let Some(name) = state.exe.jobs.name(id)
+                {
+                    state.exe.status = Some(format!("Cancelled loading {}", name));
+                }
+                state.exe.jobs.update(id, job_state);
             }
             Event::SymbolsLoaded { target_name, info } => {
                 match info {
@@ -164,23 +561,101 @@ fn main() -> Result<()> {
                             info.num_ranges, target_name
                         ));
                         state.exe.update_symbolized(target_name, info);
+                        symbols_changed = true;
                     }
                     Err(err) => {
                         state.exe.status = Some(format!("Error loading {}: {}", target_name, err))
                     }
                 };
             }
+            Event::SymbolsEvicted(evicted) => match evicted {
+                Ok(evicted) if evicted.is_empty() => {}
+                Ok(evicted) => {
+                    state.exe.status = Some(format!(
+                        "Evicted {} executable(s) to stay within the store quota",
+                        evicted.len()
+                    ));
+                    for info in &evicted {
+                        state.exe.clear_symbols(&info.file_name);
+                    }
+                    symbols_changed = true;
+                }
+                Err(err) => state.exe.status = Some(format!("Error evicting symbols: {}", err)),
+            },
+            Event::SymbolsStale(stale) => {
+                for file in &stale {
+                    store.mark_stale(file.file_id, &file.path);
+                }
+                state.exe.mark_stale(stale.iter().map(|f| f.file_id));
+                symbols_changed = true;
+                if reload_stale {
+                    state.exe.status = Some(format!(
+                        "Reloading {} executable(s) changed on disk",
+                        stale.len()
+                    ));
+                    for file in stale {
+                        jobs.submit(file.path, Some(file.name));
+                    }
+                } else {
+                    state.exe.status = Some(format!(
+                        "{} executable(s) changed on disk; their symbols are stale",
+                        stale.len()
+                    ));
+                }
+            }
+            Event::ProcessScanned { pid, result } => match result {
+                Ok((0, skipped)) => {
+                    state.exe.status = Some(format!(
+                        "All {} executable mapping(s) of PID {} already loaded",
+                        skipped, pid
+                    ))
+                }
+                Ok((queued, skipped)) => {
+                    state.exe.status = Some(format!(
+                        "Loading {} executable mapping(s) of PID {} ({} already loaded)",
+                        queued, pid, skipped
+                    ))
+                }
+                Err(err) => {
+                    state.exe.status =
+                        Some(format!("Error reading mappings of PID {}: {}", pid, err))
+                }
+            },
             Event::SymbolsRemoved { name, error } => {
+                let removed = error.is_none();
                 state.exe.status = Some(
                     error
                         .map(|err| format!("Error removing {}: {}", name, err))
                         .unwrap_or(format!("Removed symbols for {}", name)),
                 );
-                state.exe.clear_symbols(&name);
+                if removed {
+                    state.exe.clear_symbols(&name);
+                    symbols_changed = true;
+                }
+            }
+            Event::StoreCompacted(error) => {
+                state.exe.status = Some(
+                    error
+                        .map(|err| format!("Error compacting symbol store: {}", err))
+                        .unwrap_or("Compacted symbol store".to_string()),
+                );
+            }
+            Event::StoreListed(files) => match files {
+                Ok(files) => state.exe.sync_store(files),
+                Err(err) => state.exe.status = Some(format!("Error reading symbol store: {}", err)),
+            },
+            Event::StoreUsage(usage) => state.exe.usage = usage,
+            Event::PerfMapsChanged => {
+                perf_maps_pending |= state
+                    .fg
+                    .graph
+                    .unresolved()
+                    .any(|frame| grpc::resolves_with_perf_maps(&perf_maps, frame));
             }
         }
     }
 
     tui.exit()?;
+    store.flush_lookups()?;
     Ok(())
 }
@@ -0,0 +1,315 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock, mpsc};
+use std::time::Duration;
+
+use crate::tui::event::Event;
+
+const JITDUMP_MAGIC: u32 = 0x4A69_5444;
+const JITDUMP_HEADER_SIZE: usize = 40;
+const JIT_CODE_LOAD: u32 = 0;
+const JIT_CODE_CLOSE: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    /// `perf-<pid>.map`: one `START SIZE name` line per symbol, hex numbers.
+    Text,
+    /// `jit-<pid>.dump`: binary jitdump records.
+    JitDump,
+}
+
+struct Tracked {
+    pid: Option<u32>,
+    format: Format,
+    /// Bytes consumed so far; maps are append-only, so only the tail is
+    /// parsed on refresh.
+    offset: u64,
+}
+
+struct JitSymbol {
+    end: u64,
+    name: String,
+}
+
+/// JIT symbols read from perf map and jitdump files written by runtime
+/// agents (Node `--perf-basic-prof`, .NET `DOTNET_PerfMapEnabled`,
+/// perf-map-agent for the JVM, ...).
+///
+/// Each configured path is either such a file or a directory scanned for
+/// `perf-<pid>.map`, `perf-<pid>.dump` and `jit-<pid>.dump`. Symbols are
+/// kept per process when the file name carries a PID.
+#[derive(Default)]
+pub struct PerfMaps {
+    sources: Vec<PathBuf>,
+    files: Mutex<HashMap<PathBuf, Tracked>>,
+    symbols: RwLock<HashMap<Option<u32>, BTreeMap<u64, JitSymbol>>>,
+}
+
+impl PerfMaps {
+    pub fn new(sources: Vec<PathBuf>) -> Self {
+        Self {
+            sources,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.sources.is_empty()
+    }
+
+    /// Pick up new files and read whatever was appended to known ones.
+    /// Returns whether any symbol was added.
+    pub fn refresh(&self) -> bool {
+        let mut files = self.files.lock().unwrap();
+        for source in &self.sources {
+            if source.is_dir() {
+                let Ok(entries) = std::fs::read_dir(source) else {
+                    continue;
+                };
+                for entry in entries.flatten() {
+                    let name = entry.file_name();
+                    if let Some((pid, format)) = classify(&name.to_string_lossy()) {
+                        files.entry(entry.path()).or_insert(Tracked {
+                            pid: Some(pid),
+                            format,
+                            offset: 0,
+                        });
+                    }
+                }
+            } else if !files.contains_key(source) {
+                let name = source
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let (pid, format) = match classify(&name) {
+                    Some((pid, format)) => (Some(pid), format),
+                    None if name.ends_with(".dump") => (None, Format::JitDump),
+                    None => (None, Format::Text),
+                };
+                files.insert(
+                    source.clone(),
+                    Tracked {
+                        pid,
+                        format,
+                        offset: 0,
+                    },
+                );
+            }
+        }
+
+        let mut added = false;
+        for (path, tracked) in files.iter_mut() {
+            let Ok(tail) = read_tail(path, tracked) else {
+                continue;
+            };
+            let (entries, consumed) = match tracked.format {
+                Format::Text => parse_perf_map(&tail),
+                Format::JitDump => parse_jitdump(&tail, tracked.offset == 0),
+            };
+            tracked.offset += consumed as u64;
+            if entries.is_empty() {
+                continue;
+            }
+            added = true;
+            let mut symbols = self.symbols.write().unwrap();
+            let map = symbols.entry(tracked.pid).or_default();
+            for (start, size, name) in entries {
+                map.insert(
+                    start,
+                    JitSymbol {
+                        end: start.saturating_add(size),
+                        name,
+                    },
+                );
+            }
+        }
+        added
+    }
+
+    /// Name of the JIT symbol covering `addr`, preferring maps of `pid`.
+    pub fn lookup(&self, pid: Option<u32>, addr: u64) -> Option<String> {
+        let symbols = self.symbols.read().unwrap();
+        let find = |key: Option<u32>| {
+            let (_, sym) = symbols.get(&key)?.range(..=addr).next_back()?;
+            (addr < sym.end).then(|| sym.name.clone())
+        };
+        pid.and_then(|pid| find(Some(pid))).or_else(|| find(None))
+    }
+}
+
+/// Recognise `perf-<pid>.map`, `perf-<pid>.dump` and `jit-<pid>.dump`.
+fn classify(file_name: &str) -> Option<(u32, Format)> {
+    let (stem, format) = if let Some(stem) = file_name.strip_suffix(".map") {
+        (stem.strip_prefix("perf-")?, Format::Text)
+    } else {
+        let stem = file_name.strip_suffix(".dump")?;
+        let stem = stem
+            .strip_prefix("perf-")
+            .or_else(|| stem.strip_prefix("jit-"))?;
+        (stem, Format::JitDump)
+    };
+    Some((stem.parse().ok()?, format))
+}
+
+/// Read everything past `tracked.offset`, starting over if the file shrank
+/// (i.e. was rewritten).
+fn read_tail(path: &Path, tracked: &mut Tracked) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < tracked.offset {
+        tracked.offset = 0;
+    }
+    let mut tail = Vec::new();
+    if len > tracked.offset {
+        file.seek(SeekFrom::Start(tracked.offset))?;
+        file.read_to_end(&mut tail)?;
+    }
+    Ok(tail)
+}
+
+/// Parse complete lines of a perf map; returns `(start, size, name)`
+/// entries and the number of bytes consumed.
+fn parse_perf_map(data: &[u8]) -> (Vec<(u64, u64, String)>, usize) {
+    let Some(consumed) = data.iter().rposition(|&b| b == b'\n').map(|i| i + 1) else {
+        return (Vec::new(), 0);
+    };
+    let hex = |s: &str| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok();
+    let entries = String::from_utf8_lossy(&data[..consumed])
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ' ');
+            let start = hex(parts.next()?)?;
+            let size = hex(parts.next()?)?;
+            let name = parts.next()?.trim();
+            (!name.is_empty()).then(|| (start, size, name.to_string()))
+        })
+        .collect();
+    (entries, consumed)
+}
+
+/// Parse complete `JIT_CODE_LOAD` records of a native-endian jitdump
+/// file; returns `(start, size, name)` entries and the bytes consumed.
+fn parse_jitdump(data: &[u8], at_start: bool) -> (Vec<(u64, u64, String)>, usize) {
+    let u32_at = |pos: usize| {
+        data.get(pos..pos + 4)
+            .map(|b| u32::from_ne_bytes(b.try_into().unwrap()))
+    };
+    let u64_at = |pos: usize| {
+        data.get(pos..pos + 8)
+            .map(|b| u64::from_ne_bytes(b.try_into().unwrap()))
+    };
+
+    let mut pos = 0;
+    if at_start {
+        match (u32_at(0), u32_at(8)) {
+            (Some(JITDUMP_MAGIC), Some(header_size)) => {
+                pos = (header_size as usize).max(JITDUMP_HEADER_SIZE);
+            }
+            _ => return (Vec::new(), 0),
+        }
+        if data.len() < pos {
+            return (Vec::new(), 0);
+        }
+    }
+
+    let mut entries = Vec::new();
+    while let (Some(id), Some(size)) = (u32_at(pos), u32_at(pos + 4)) {
+        let size = size as usize;
+        if size < 16 || data.len() < pos + size {
+            break;
+        }
+        match id {
+            JIT_CODE_LOAD => {
+                // header(16) pid(4) tid(4) vma(8) code_addr(8) code_size(8) index(8) name\0 code
+                let record = &data[pos..pos + size];
+                if let (Some(addr), Some(code_size)) = (u64_at(pos + 32), u64_at(pos + 40))
+                    && let Some(name) = record.get(56..)
+                {
+                    let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+                    let name = String::from_utf8_lossy(&name[..end]).into_owned();
+                    entries.push((addr, code_size, name));
+                }
+            }
+            JIT_CODE_CLOSE => {
+                pos += size;
+                break;
+            }
+            _ => {}
+        }
+        pos += size;
+    }
+    (entries, pos)
+}
+
+/// Refresh `perf_maps` every `interval` on a background thread, reporting
+/// new symbols through [`Event::PerfMapsChanged`].
+pub fn watch(perf_maps: Arc<PerfMaps>, sender: mpsc::Sender<Event>, interval: Duration) {
+    std::thread::spawn(move || {
+        loop {
+            if perf_maps.refresh() && sender.send(Event::PerfMapsChanged).is_err() {
+                return;
+            }
+            std::thread::sleep(interval);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perf_map_keeps_partial_line_for_next_refresh() {
+        let data = b"7f00a000 40 LazyCompile:~main app.js:1\n0x7f00b000 0x10 Foo::bar\n7f00c";
+        let (entries, consumed) = parse_perf_map(data);
+        assert_eq!(
+            entries,
+            vec![
+                (0x7f00a000, 0x40, "LazyCompile:~main app.js:1".to_string()),
+                (0x7f00b000, 0x10, "Foo::bar".to_string()),
+            ]
+        );
+        assert_eq!(&data[consumed..], b"7f00c");
+    }
+
+    #[test]
+    fn jitdump_code_load_records() {
+        let mut data = Vec::new();
+        data.extend(JITDUMP_MAGIC.to_ne_bytes());
+        data.extend(1u32.to_ne_bytes());
+        data.extend((JITDUMP_HEADER_SIZE as u32).to_ne_bytes());
+        data.resize(JITDUMP_HEADER_SIZE, 0);
+
+        let name = b"Interpreter::run\0";
+        let code = [0x90u8; 4];
+        let size = 56 + name.len() + code.len();
+        data.extend(JIT_CODE_LOAD.to_ne_bytes());
+        data.extend((size as u32).to_ne_bytes());
+        data.extend(0u64.to_ne_bytes()); // timestamp
+        data.extend(42u32.to_ne_bytes()); // pid
+        data.extend(42u32.to_ne_bytes()); // tid
+        data.extend(0x1000u64.to_ne_bytes()); // vma
+        data.extend(0x1000u64.to_ne_bytes()); // code_addr
+        data.extend((code.len() as u64).to_ne_bytes());
+        data.extend(0u64.to_ne_bytes()); // code_index
+        data.extend(name);
+        data.extend(code);
+        let complete = data.len();
+        data.extend(JIT_CODE_LOAD.to_ne_bytes()); // truncated record
+
+        let (entries, consumed) = parse_jitdump(&data, true);
+        assert_eq!(entries, vec![(0x1000, 4, "Interpreter::run".to_string())]);
+        assert_eq!(consumed, complete);
+    }
+
+    #[test]
+    fn classifies_perf_map_file_names() {
+        assert_eq!(classify("perf-1234.map"), Some((1234, Format::Text)));
+        assert_eq!(classify("jit-99.dump"), Some((99, Format::JitDump)));
+        assert_eq!(classify("perf-99.dump"), Some((99, Format::JitDump)));
+        assert_eq!(classify("perf-abc.map"), None);
+        assert_eq!(classify("notes.txt"), None);
+    }
+}
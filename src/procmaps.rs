@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// An executable, file-backed mapping of a running process.
+pub struct ProcMapping {
+    /// Path as seen inside the process (mount namespace).
+    pub path: String,
+    /// Path reachable from this host, through `/proc/<pid>/root`.
+    pub host_path: PathBuf,
+}
+
+/// Read `/proc/<pid>/maps` and return each distinct executable file mapping.
+///
+/// Paths are resolved through `/proc/<pid>/root` so binaries of processes
+/// running in containers can be opened from the host.
+pub fn executable_mappings(pid: u32) -> std::io::Result<Vec<ProcMapping>> {
+    let maps = std::fs::read_to_string(format!("/proc/{pid}/maps"))?;
+    let root = PathBuf::from(format!("/proc/{pid}/root"));
+    Ok(parse_executable_paths(&maps)
+        .into_iter()
+        .map(|path| ProcMapping {
+            host_path: root.join(path.trim_start_matches('/')),
+            path,
+        })
+        .collect())
+}
+
+/// Extract distinct executable file paths from `/proc/<pid>/maps` content.
+///
+/// Each line is `start-end perms offset dev inode [pathname]`; anonymous
+/// and pseudo mappings (`[vdso]`, `[heap]`, ...) and deleted files are
+/// skipped.
+fn parse_executable_paths(maps: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    maps.lines()
+        .filter_map(|line| {
+            let mut rest = line;
+            let mut fields = [""; 5];
+            for field in &mut fields {
+                let trimmed = rest.trim_start();
+                let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+                *field = &trimmed[..end];
+                rest = &trimmed[end..];
+            }
+            let (perms, path) = (fields[1], rest.trim());
+            if !perms.contains('x') || !path.starts_with('/') || path.ends_with(" (deleted)") {
+                return None;
+            }
+            seen.insert(path).then(|| path.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_executable_file_mappings_once() {
+        let maps = "\
+55d0c0a00000-55d0c0a28000 r--p 00000000 fd:01 1048 /usr/bin/server
+55d0c0a28000-55d0c0b00000 r-xp 00028000 fd:01 1048 /usr/bin/server
+7f1e2c000000-7f1e2c021000 rw-p 00000000 00:00 0
+7f1e2d200000-7f1e2d3a0000 r-xp 00028000 fd:01 2201 /usr/lib/libc.so.6
+7f1e2d400000-7f1e2d401000 r-xp 00000000 fd:01 3301 /opt/my app/lib plugin.so
+7f1e2d500000-7f1e2d501000 r-xp 00000000 fd:01 3302 /tmp/old.so (deleted)
+7ffd3e5f0000-7ffd3e5f2000 r-xp 00000000 00:00 0 [vdso]
+";
+        assert_eq!(
+            parse_executable_paths(maps),
+            vec![
+                "/usr/bin/server".to_string(),
+                "/usr/lib/libc.so.6".to_string(),
+                "/opt/my app/lib plugin.so".to_string(),
+            ]
+        );
+    }
+}
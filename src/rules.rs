@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Debug)]
+enum RuleKind {
+    Hide,
+    Fold,
+    Rename(String),
+}
+
+#[derive(Clone, Debug)]
+struct FrameRule {
+    pattern: Regex,
+    kind: RuleKind,
+}
+
+/// What the rules do with one frame.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FrameAction<'a> {
+    /// Remove the frame; its callees move up to its caller.
+    Hide,
+    /// Keep the frame, possibly renamed.
+    Keep(Cow<'a, str>),
+    /// Keep the frame, possibly renamed, and fold everything it calls into
+    /// its self value.
+    Fold(Cow<'a, str>),
+}
+
+/// Frame hiding, folding and renaming rules, one per line:
+///
+/// ```text
+/// # runtime plumbing
+/// hide ^std::rt::lang_start
+/// hide ^__libc_start_main$
+/// fold ^tokio::runtime::
+/// rename ^(.*)::\{\{closure\}\}$=$1::closure
+/// ```
+///
+/// Rules are tried in file order: renames rewrite the name seen by later
+/// rules, and the first matching `hide` or `fold` decides.
+#[derive(Clone, Debug, Default)]
+pub struct FrameRules {
+    rules: Vec<FrameRule>,
+    pub enabled: bool,
+}
+
+impl FrameRules {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: String| Error::Rule {
+                line: i + 1,
+                message,
+            };
+            let (kind, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            let (pattern, kind) = match kind {
+                "hide" => (rest, RuleKind::Hide),
+                "fold" => (rest, RuleKind::Fold),
+                "rename" => {
+                    let (pattern, replacement) = rest.rsplit_once('=').ok_or_else(|| {
+                        invalid("expected `rename PATTERN=REPLACEMENT`".to_string())
+                    })?;
+                    (pattern, RuleKind::Rename(replacement.to_string()))
+                }
+                other => return Err(invalid(format!("unknown rule `{other}`"))),
+            };
+            if pattern.is_empty() {
+                return Err(invalid("missing pattern".to_string()));
+            }
+            let pattern = Regex::new(pattern).map_err(|e| invalid(e.to_string()))?;
+            rules.push(FrameRule { pattern, kind });
+        }
+        Ok(Self {
+            rules,
+            enabled: true,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn apply<'a>(&self, name: &'a str) -> FrameAction<'a> {
+        let mut name = Cow::Borrowed(name);
+        if !self.enabled {
+            return FrameAction::Keep(name);
+        }
+        for rule in &self.rules {
+            match &rule.kind {
+                RuleKind::Rename(replacement) => {
+                    if let Cow::Owned(renamed) = rule.pattern.replace(&name, replacement.as_str()) {
+                        name = Cow::Owned(renamed);
+                    }
+                }
+                RuleKind::Hide if rule.pattern.is_match(&name) => return FrameAction::Hide,
+                RuleKind::Fold if rule.pattern.is_match(&name) => return FrameAction::Fold(name),
+                RuleKind::Hide | RuleKind::Fold => {}
+            }
+        }
+        FrameAction::Keep(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_apply_in_file_order() {
+        let rules = FrameRules::parse(
+            "# plumbing\nhide ^__libc_start_main$\n\nrename ^(.*)::\\{\\{closure\\}\\}$=$1::closure\nfold ^tokio::\n",
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules.apply("__libc_start_main"), FrameAction::Hide);
+        assert_eq!(
+            rules.apply("app::run::{{closure}}"),
+            FrameAction::Keep("app::run::closure".into())
+        );
+        assert_eq!(
+            rules.apply("tokio::spawn::{{closure}}"),
+            FrameAction::Fold("tokio::spawn::closure".into())
+        );
+        assert_eq!(rules.apply("main"), FrameAction::Keep("main".into()));
+
+        let disabled = FrameRules {
+            enabled: false,
+            ..rules
+        };
+        assert_eq!(
+            disabled.apply("__libc_start_main"),
+            FrameAction::Keep("__libc_start_main".into())
+        );
+
+        assert!(matches!(
+            FrameRules::parse("hide main\nskip x\n"),
+            Err(Error::Rule { line: 2, .. })
+        ));
+        assert!(matches!(
+            FrameRules::parse("rename x"),
+            Err(Error::Rule { line: 1, .. })
+        ));
+        assert!(matches!(
+            FrameRules::parse("hide ("),
+            Err(Error::Rule { line: 1, .. })
+        ));
+    }
+}
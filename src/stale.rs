@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+
+use crate::storage::{FileId, SymbolStore};
+use crate::tui::event::Event;
+
+/// A stored file whose source path now holds a different binary.
+pub struct StaleFile {
+    pub file_id: FileId,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Compare every stored file against its recorded source path.
+///
+/// Files whose size and mtime still match are trusted without hashing.
+/// Missing paths are not reported: `/proc/<pid>/root` paths disappear with
+/// their process, which says nothing about the symbols.
+pub fn find_stale(store: &SymbolStore) -> crate::Result<Vec<StaleFile>> {
+    let mut stale = Vec::new();
+    for info in store.list_files()? {
+        let Some(details) = info.details else {
+            continue;
+        };
+        let path = PathBuf::from(details.path);
+        let Ok(meta) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        if meta.len() == details.file_size && mtime == details.mtime {
+            continue;
+        }
+        if FileId::from_path(&path).is_ok_and(|id| id != info.file_id) {
+            stale.push(StaleFile {
+                file_id: info.file_id,
+                name: info.file_name,
+                path,
+            });
+        }
+    }
+    Ok(stale)
+}
+
+/// Poll source paths every `interval` on a background thread, reporting
+/// newly stale files through [`Event::SymbolsStale`].
+pub fn watch(store: Arc<SymbolStore>, sender: mpsc::Sender<Event>, interval: Duration) {
+    std::thread::spawn(move || {
+        let mut reported = HashSet::new();
+        loop {
+            if let Ok(stale) = find_stale(&store) {
+                let fresh: Vec<StaleFile> = stale
+                    .into_iter()
+                    .filter(|s| reported.insert(s.file_id))
+                    .collect();
+                if !fresh.is_empty() && sender.send(Event::SymbolsStale(fresh)).is_err() {
+                    return;
+                }
+            }
+            std::thread::sleep(interval);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbolizer::{SymChunk, SymSource};
+
+    fn import(store: &SymbolStore, id: u128, path: &std::path::Path) {
+        let meta = std::fs::metadata(path).unwrap();
+        let source = SymSource {
+            file_size: meta.len(),
+            mtime: meta
+                .modified()
+                .unwrap()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            ..Default::default()
+        };
+        let mut import = store.begin_import(FileId::from(id), path).unwrap();
+        import.write_chunk(&SymChunk::default()).unwrap();
+        import.finish(&source).unwrap();
+    }
+
+    #[test]
+    fn reports_files_rewritten_since_import() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SymbolStore::open(tmp.path().join("store")).unwrap();
+        let unchanged = tmp.path().join("unchanged");
+        let rebuilt = tmp.path().join("rebuilt");
+        std::fs::write(&unchanged, b"v1").unwrap();
+        std::fs::write(&rebuilt, b"v1").unwrap();
+        import(&store, 1, &unchanged);
+        import(&store, 2, &rebuilt);
+
+        std::fs::write(&rebuilt, b"version 2").unwrap();
+        let stale = find_stale(&store).unwrap();
+
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].file_id, FileId::from(2));
+        assert_eq!(stale[0].path, rebuilt);
+    }
+}
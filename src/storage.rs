@@ -1,6 +1,7 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use fjall::{Database, Keyspace, KeyspaceCreateOptions};
 use symblib::VirtAddr;
@@ -8,10 +9,22 @@ pub use symblib::fileid::FileId;
 use zerocopy::byteorder::{BigEndian, U16, U32, U64, U128};
 use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
 
-use crate::symbolizer::{FileSym, SymRange};
+use crate::cache::{CacheStats, LookupCache};
+use crate::symbolizer::{LoadSegment, SymChunk, SymRange, SymSource};
 
 const NONE_REF: u32 = u32::MAX;
 
+/// Entries per batch when moving or discarding a staged file's symbols, so
+/// no single batch holds all of them.
+const BATCH_KEYS: usize = 64 * 1024;
+
+/// How often last-lookup times should be written back with
+/// [`SymbolStore::flush_lookups`].
+///
+/// Lookups happen for every native frame, so they only update the times
+/// kept in memory.
+pub const LOOKUP_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Big-endian key for the ranges LSM partition.
 ///
 /// Byte-level lexicographic ordering matches semantic ordering, so a
@@ -94,7 +107,150 @@ impl StringKey {
     }
 }
 
+/// Per-file housekeeping counters stored in the **stats** partition.
+///
+/// `size_bytes` is the logical size (keys + values) of everything written
+/// for the file; `last_lookup` is a unix timestamp in seconds (`0` = never).
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct FileStats {
+    size_bytes: U64<BigEndian>,
+    last_lookup: U64<BigEndian>,
+}
+
+impl FileStats {
+    fn new(size_bytes: u64, last_lookup: u64) -> Self {
+        Self {
+            size_bytes: U64::new(size_bytes),
+            last_lookup: U64::new(last_lookup),
+        }
+    }
+}
+
+/// Progress marker stored in the **pending** partition while a file is
+/// being imported, followed by the source path as UTF-8.
+///
+/// Every chunk is committed together with an updated marker, so an import
+/// interrupted by a crash can resume after its last committed chunk. A
+/// re-import also records how far swapping in the staged symbols got.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct PendingImport {
+    ranges: U64<BigEndian>,
+    strings: U32<BigEndian>,
+    size_bytes: U64<BigEndian>,
+    phase: u8,
+}
+
+impl PendingImport {
+    /// Chunks are being written.
+    const STAGING: u8 = 0;
+    /// The previous version's symbols are being removed.
+    const REMOVING_OLD: u8 = 1;
+    /// The staged symbols are being moved into place.
+    const MOVING_STAGED: u8 = 2;
+
+    fn encode(ranges: u64, strings: u32, size_bytes: u64, phase: u8, path: &Path) -> Vec<u8> {
+        let marker = Self {
+            ranges: U64::new(ranges),
+            strings: U32::new(strings),
+            size_bytes: U64::new(size_bytes),
+            phase,
+        };
+        let mut value = marker.as_bytes().to_vec();
+        value.extend_from_slice(path.to_string_lossy().as_bytes());
+        value
+    }
+
+    fn decode(value: &[u8]) -> Option<(&Self, PathBuf)> {
+        let (marker, path) = Self::ref_from_prefix(value).ok()?;
+        Some((
+            marker,
+            PathBuf::from(String::from_utf8_lossy(path).into_owned()),
+        ))
+    }
+}
+
+/// Fixed part of a **details** value, followed by the build ID (hex),
+/// architecture, comma-separated extractors and the source path.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct DetailsHeader {
+    file_size: U64<BigEndian>,
+    mtime: U64<BigEndian>,
+    imported_at: U64<BigEndian>,
+    build_id_len: U16<BigEndian>,
+    arch_len: U16<BigEndian>,
+    extractors_len: U16<BigEndian>,
+}
+
+/// One `PT_LOAD` segment in the **segments** partition.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct SegmentValue {
+    file_offset: U64<BigEndian>,
+    vaddr: U64<BigEndian>,
+    file_size: U64<BigEndian>,
+}
+
+/// Where a stored file's symbols came from.
+#[derive(Clone, Default)]
+pub struct FileDetails {
+    /// Full path the symbols were imported from.
+    pub path: String,
+    /// GNU build ID as lowercase hex.
+    pub build_id: Option<String>,
+    pub arch: String,
+    pub file_size: u64,
+    pub mtime: u64,
+    /// Extractors that contributed ranges, in priority order.
+    pub extractors: Vec<String>,
+    pub imported_at: u64,
+}
+
+impl FileDetails {
+    fn encode(&self) -> Vec<u8> {
+        let build_id = self.build_id.as_deref().unwrap_or_default();
+        let extractors = self.extractors.join(",");
+        let header = DetailsHeader {
+            file_size: U64::new(self.file_size),
+            mtime: U64::new(self.mtime),
+            imported_at: U64::new(self.imported_at),
+            build_id_len: U16::new(build_id.len() as u16),
+            arch_len: U16::new(self.arch.len() as u16),
+            extractors_len: U16::new(extractors.len() as u16),
+        };
+        let mut value = header.as_bytes().to_vec();
+        for part in [build_id, &self.arch, &extractors, &self.path] {
+            value.extend_from_slice(part.as_bytes());
+        }
+        value
+    }
+
+    fn decode(value: &[u8]) -> Option<Self> {
+        let (header, rest) = DetailsHeader::ref_from_prefix(value).ok()?;
+        let (build_id, rest) = rest.split_at_checked(header.build_id_len.get() as usize)?;
+        let (arch, rest) = rest.split_at_checked(header.arch_len.get() as usize)?;
+        let (extractors, path) = rest.split_at_checked(header.extractors_len.get() as usize)?;
+        let text = |b: &[u8]| String::from_utf8_lossy(b).into_owned();
+        Some(Self {
+            path: text(path),
+            build_id: (!build_id.is_empty()).then(|| text(build_id)),
+            arch: text(arch),
+            file_size: header.file_size.get(),
+            mtime: header.mtime.get(),
+            extractors: text(extractors)
+                .split(',')
+                .filter(|e| !e.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            imported_at: header.imported_at.get(),
+        })
+    }
+}
+
 /// Resolved symbol information for a single inline depth level.
+#[derive(Clone)]
 pub struct ResolvedFrame {
     pub func: String,
     pub depth: u16,
@@ -106,20 +262,57 @@ pub struct ExecutableInfo {
     pub file_id: FileId,
     pub file_name: String,
     pub num_ranges: u32,
+    pub size_bytes: u64,
+    pub last_lookup: u64,
+    /// Missing for files imported before details were recorded.
+    pub details: Option<FileDetails>,
+}
+
+/// Aggregate disk usage of the store.
+#[derive(Clone, Copy, Default)]
+pub struct StoreUsage {
+    /// Sum of the logical per-file sizes.
+    pub total_bytes: u64,
+    /// Bytes used by the data directory on disk (includes LSM overhead).
+    pub disk_bytes: u64,
+    pub quota: Option<u64>,
+    pub cache: CacheStats,
 }
 
 /// Persistent symbol store backed by fjall (LSM-tree).
 ///
-/// Three partitions:
+/// Seven partitions:
 ///   - **ranges**: `RangeKey -> RangeValue` (fixed 26-byte key, 20-byte value)
 ///   - **strings**: `StringKey -> raw UTF-8` (fixed 20-byte key, variable value)
 ///   - **files**: `U128<BE> -> num_ranges(4) + filename` (executable metadata)
+///   - **stats**: `U128<BE> -> FileStats` (size accounting and last lookup)
+///   - **details**: `U128<BE> -> DetailsHeader + strings` (provenance)
+///   - **segments**: `U128<BE> -> [SegmentValue]` (address translation)
+///   - **pending**: `U128<BE> -> PendingImport + path` (imports in progress)
+///
+/// A file only becomes visible to lookups once its import finished and its
+/// **files** entry is written; ranges of pending imports are ignored.
 pub struct SymbolStore {
     db: Database,
+    path: PathBuf,
+    quota: Option<u64>,
     ranges: Keyspace,
     strings: Keyspace,
     files: Keyspace,
+    stats: Keyspace,
+    details: Keyspace,
+    segments: Keyspace,
+    pending: Keyspace,
+    importing: RwLock<HashSet<FileId>>,
     basename_index: RwLock<HashMap<String, FileId>>,
+    /// Source paths of stale files, replaced once a new version is loaded.
+    stale: RwLock<HashMap<PathBuf, FileId>>,
+    /// Load segments per file, read from **segments** on first use.
+    load_segments: RwLock<HashMap<FileId, Vec<LoadSegment>>>,
+    /// Last lookup per file, ahead of **stats** until the next
+    /// [`SymbolStore::flush_lookups`].
+    last_lookup: RwLock<HashMap<FileId, u64>>,
+    cache: LookupCache,
 }
 
 impl SymbolStore {
@@ -133,16 +326,31 @@ impl SymbolStore {
         let ranges = db.keyspace("ranges", KeyspaceCreateOptions::default)?;
         let strings = db.keyspace("strings", KeyspaceCreateOptions::default)?;
         let files = db.keyspace("files", KeyspaceCreateOptions::default)?;
+        let stats = db.keyspace("stats", KeyspaceCreateOptions::default)?;
+        let details = db.keyspace("details", KeyspaceCreateOptions::default)?;
+        let segments = db.keyspace("segments", KeyspaceCreateOptions::default)?;
+        let pending = db.keyspace("pending", KeyspaceCreateOptions::default)?;
 
         let store = Self {
             db,
+            path: path.as_ref().to_path_buf(),
+            quota: None,
             ranges,
             strings,
             files,
+            stats,
+            details,
+            segments,
+            pending,
+            importing: RwLock::new(HashSet::new()),
             basename_index: RwLock::new(HashMap::new()),
+            stale: RwLock::new(HashMap::new()),
+            load_segments: RwLock::new(HashMap::new()),
+            last_lookup: RwLock::new(HashMap::new()),
+            cache: LookupCache::default(),
         };
 
-        // Rebuild in-memory basename index from persisted metadata.
+        // Rebuild in-memory indexes from persisted metadata.
         for info in store.list_files()? {
             let basename = basename_of(&info.file_name);
             store
@@ -150,59 +358,312 @@ impl SymbolStore {
                 .write()
                 .unwrap()
                 .insert(basename, info.file_id);
+            store
+                .last_lookup
+                .write()
+                .unwrap()
+                .insert(info.file_id, info.last_lookup);
+        }
+        store.backfill_stats()?;
+        for (file_id, _) in store.pending_imports()? {
+            if !store.contains(file_id) || store.swap_phase(file_id)? != PendingImport::STAGING {
+                store.importing.write().unwrap().insert(file_id);
+            }
         }
 
         Ok(store)
     }
 
-    /// Atomically persist all ranges, interned strings, and file metadata.
-    pub fn store_file_symbols(&self, file_sym: &FileSym, path: &Path) -> crate::Result<()> {
-        let fid: u128 = file_sym.file_id.into();
-        let mut batch = self.db.batch();
+    /// Limit the logical store size; older files are evicted by
+    /// [`SymbolStore::enforce_quota`] once it is exceeded.
+    pub fn with_quota(mut self, quota: Option<u64>) -> Self {
+        self.quota = quota;
+        self
+    }
 
-        for (idx, s) in file_sym.strings.iter().enumerate() {
-            batch.insert(
-                &self.strings,
-                StringKey::new(fid, idx as u32).as_bytes(),
-                s.as_bytes(),
-            );
+    /// Compute stats for files stored before size accounting existed.
+    fn backfill_stats(&self) -> crate::Result<()> {
+        let now = unix_now();
+        for guard in self.files.range::<Vec<u8>, _>(..) {
+            let (kb, vb) = guard.into_inner()?;
+            if self.stats.get(&*kb)?.is_some() {
+                continue;
+            }
+            let Ok(fid) = U128::<BigEndian>::ref_from_bytes(&kb) else {
+                continue;
+            };
+            let size = self.prefix_size(&self.ranges, fid.as_bytes())?
+                + self.prefix_size(&self.strings, fid.as_bytes())?
+                + (kb.len() + vb.len()) as u64;
+            self.stats
+                .insert(&*kb, FileStats::new(size, now).as_bytes())?;
         }
+        Ok(())
+    }
 
-        for r in &file_sym.ranges {
-            batch.insert(
-                &self.ranges,
-                RangeKey::new(fid, r.va_start, r.depth).as_bytes(),
-                RangeValue::from_range(r).as_bytes(),
-            );
+    fn prefix_size(&self, keyspace: &Keyspace, prefix: &[u8]) -> crate::Result<u64> {
+        let mut size = 0;
+        for guard in keyspace.prefix(prefix) {
+            let (kb, vb) = guard.into_inner()?;
+            size += (kb.len() + vb.len()) as u64;
         }
+        Ok(size)
+    }
 
-        let file_name = path
-            .file_name()
-            .map(|n| n.to_string_lossy().into_owned())
-            .unwrap_or_default();
-        let num_ranges = file_sym.ranges.len() as u32;
-        let mut meta_val = num_ranges.to_be_bytes().to_vec();
-        meta_val.extend_from_slice(file_name.as_bytes());
-        let fid_key = U128::<BigEndian>::new(fid);
-        batch.insert(&self.files, fid_key.as_bytes(), &meta_val);
+    /// Start (or resume) importing symbols for `file_id` from `path`.
+    ///
+    /// A new file is invisible to lookups until [`SymbolImport::finish`]; a
+    /// file that is already stored keeps serving its previous symbols while
+    /// the new ones are staged, and only then is replaced. If an earlier
+    /// import of the same file was interrupted, chunks it already committed
+    /// are skipped; extraction is deterministic, so they match.
+    pub fn begin_import(&self, file_id: FileId, path: &Path) -> crate::Result<SymbolImport<'_>> {
+        let fid: u128 = file_id.into();
+        let key = if self.contains(file_id) {
+            staging_key(fid)
+        } else {
+            self.importing.write().unwrap().insert(file_id);
+            fid
+        };
+        let key_bytes = U128::<BigEndian>::new(key);
+
+        let resumed = self.pending.get(key_bytes.as_bytes())?.and_then(|v| {
+            let (marker, _) = PendingImport::decode(&v)?;
+            Some((
+                marker.ranges.get(),
+                marker.strings.get(),
+                marker.size_bytes.get(),
+                marker.phase,
+            ))
+        });
+        let (resume_ranges, strings, size_bytes, phase) = match resumed {
+            Some(counts) => counts,
+            None => {
+                let marker = PendingImport::encode(0, 0, 0, PendingImport::STAGING, path);
+                self.pending.insert(key_bytes.as_bytes(), marker)?;
+                (0, 0, 0, PendingImport::STAGING)
+            }
+        };
 
+        Ok(SymbolImport {
+            store: self,
+            file_id,
+            key,
+            path: path.to_path_buf(),
+            resume_ranges,
+            ranges: 0,
+            strings,
+            size_bytes,
+            phase,
+        })
+    }
+
+    /// Drop the partial data of an unfinished import of `file_id`.
+    ///
+    /// A staged re-import is discarded on its own, leaving the previously
+    /// stored symbols in place, unless swapping them out already started.
+    pub fn discard_import(&self, file_id: FileId) -> crate::Result<()> {
+        let fid: u128 = file_id.into();
+        let staged = U128::<BigEndian>::new(staging_key(fid));
+        if !self.contains(file_id) || self.swap_phase(file_id)? != PendingImport::STAGING {
+            return self.remove_file_symbols(file_id);
+        }
+        self.move_prefix(&self.ranges, staged.as_bytes(), None)?;
+        self.move_prefix(&self.strings, staged.as_bytes(), None)?;
+        self.pending.remove(staged.as_bytes())?;
+        Ok(())
+    }
+
+    /// How far swapping in a staged re-import of `file_id` got;
+    /// [`PendingImport::STAGING`] if there is none.
+    fn swap_phase(&self, file_id: FileId) -> crate::Result<u8> {
+        let staged = U128::<BigEndian>::new(staging_key(file_id.into()));
+        let value = self.pending.get(staged.as_bytes())?;
+        Ok(value
+            .as_deref()
+            .and_then(PendingImport::decode)
+            .map_or(PendingImport::STAGING, |(marker, _)| marker.phase))
+    }
+
+    /// Move every entry of `keyspace` under the file key prefix `from` to
+    /// the prefix `to`, or remove it if `to` is `None`. Commits every
+    /// [`BATCH_KEYS`] entries, so this is not atomic; callers record their
+    /// progress to redo it after a crash.
+    fn move_prefix(
+        &self,
+        keyspace: &Keyspace,
+        from: &[u8],
+        to: Option<&[u8]>,
+    ) -> crate::Result<()> {
+        let mut batch = self.db.batch();
+        let mut entries = 0;
+        for guard in keyspace.prefix(from) {
+            let (kb, vb) = guard.into_inner()?;
+            if let Some(to) = to {
+                let mut key = kb.to_vec();
+                key[..to.len()].copy_from_slice(to);
+                batch.insert(keyspace, key, vb);
+            }
+            batch.remove(keyspace, kb);
+            entries += 1;
+            if entries == BATCH_KEYS {
+                std::mem::replace(&mut batch, self.db.batch()).commit()?;
+                entries = 0;
+            }
+        }
         batch.commit()?;
+        Ok(())
+    }
 
-        let bname = basename_of(&file_name);
-        self.basename_index
-            .write()
-            .unwrap()
-            .insert(bname, file_sym.file_id);
+    /// Imports that were interrupted before finishing, with their source path.
+    ///
+    /// Interrupted re-imports of stored files are reported under the file
+    /// they replace.
+    pub fn pending_imports(&self) -> crate::Result<Vec<(FileId, PathBuf)>> {
+        let mut result = Vec::new();
+        for guard in self.pending.range::<Vec<u8>, _>(..) {
+            let (kb, vb) = guard.into_inner()?;
+            if let Ok(key) = U128::<BigEndian>::ref_from_bytes(&kb)
+                && let Some((_, path)) = PendingImport::decode(&vb)
+            {
+                let replaced = FileId::from(staging_key(key.get()));
+                if self.contains(replaced) {
+                    result.push((replaced, path));
+                } else {
+                    result.push((FileId::from(key.get()), path));
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Evict least recently looked-up files until the store fits its quota.
+    ///
+    /// `keep` is never evicted, so a freshly loaded file survives even when it
+    /// alone exceeds the quota. Returns metadata of the evicted files.
+    pub fn enforce_quota(&self, keep: FileId) -> crate::Result<Vec<ExecutableInfo>> {
+        let Some(quota) = self.quota else {
+            return Ok(Vec::new());
+        };
+
+        let mut files = self.list_files()?;
+        let mut total: u64 = files.iter().map(|f| f.size_bytes).sum();
+        if total <= quota {
+            return Ok(Vec::new());
+        }
 
+        files.sort_by_key(|f| f.last_lookup);
+        let mut evicted = Vec::new();
+        for info in files {
+            if total <= quota {
+                break;
+            }
+            if info.file_id == keep {
+                continue;
+            }
+            self.remove_file_symbols(info.file_id)?;
+            total = total.saturating_sub(info.size_bytes);
+            evicted.push(info);
+        }
+
+        if !evicted.is_empty() {
+            self.compact()?;
+        }
+        Ok(evicted)
+    }
+
+    /// Major-compact every partition so tombstones left by removals are
+    /// dropped and disk space is reclaimed.
+    pub fn compact(&self) -> crate::Result<()> {
+        for keyspace in [
+            &self.ranges,
+            &self.strings,
+            &self.files,
+            &self.stats,
+            &self.details,
+            &self.segments,
+            &self.pending,
+        ] {
+            keyspace.major_compact()?;
+        }
+        Ok(())
+    }
+
+    /// Logical and on-disk size of the store.
+    pub fn usage(&self) -> StoreUsage {
+        let total_bytes = self
+            .list_files()
+            .map(|files| files.iter().map(|f| f.size_bytes).sum())
+            .unwrap_or(0);
+        StoreUsage {
+            total_bytes,
+            disk_bytes: dir_size(&self.path),
+            quota: self.quota,
+            cache: self.cache.stats(),
+        }
+    }
+
+    /// Record a lookup for LRU eviction, in memory only.
+    fn touch(&self, file_id: FileId) {
+        let now = unix_now();
+        if self.last_lookup.read().unwrap().get(&file_id) == Some(&now) {
+            return;
+        }
+        self.last_lookup.write().unwrap().insert(file_id, now);
+    }
+
+    /// Write last-lookup times recorded since the previous flush to
+    /// **stats**, so they survive a restart.
+    pub fn flush_lookups(&self) -> crate::Result<()> {
+        let times: Vec<(FileId, u64)> = self
+            .last_lookup
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(&id, &t)| (id, t))
+            .collect();
+        let mut batch = self.db.batch();
+        for (file_id, time) in times {
+            let fid_key = U128::<BigEndian>::new(file_id.into());
+            if let Some(vb) = self.stats.get(fid_key.as_bytes())?
+                && let Ok(stats) = FileStats::ref_from_bytes(&vb)
+                && stats.last_lookup.get() < time
+            {
+                let updated = FileStats::new(stats.size_bytes.get(), time);
+                batch.insert(&self.stats, fid_key.as_bytes(), updated.as_bytes());
+            }
+        }
+        batch.commit()?;
         Ok(())
     }
 
     /// Find all symbol frames covering `addr` in the given file.
     ///
+    /// Results (including misses) are served from the in-memory
+    /// [`LookupCache`] when possible.
+    pub fn lookup(&self, file_id: FileId, addr: VirtAddr) -> crate::Result<Vec<ResolvedFrame>> {
+        if self.importing.read().unwrap().contains(&file_id) {
+            return Ok(Vec::new());
+        }
+        self.touch(file_id);
+        if let Some(frames) = self.cache.frames(file_id, addr) {
+            return Ok(frames);
+        }
+        let epoch = self.cache.epoch();
+        let frames = self.lookup_ranges(file_id, addr, epoch)?;
+        self.cache.insert_frames(epoch, file_id, addr, &frames);
+        Ok(frames)
+    }
+
     /// Scans backwards from `(file_id, addr, MAX_DEPTH)` until a depth-0
     /// containing range is found, collecting inline frames along the way.
     /// Returns frames sorted by depth (outermost first).
-    pub fn lookup(&self, file_id: FileId, addr: VirtAddr) -> crate::Result<Vec<ResolvedFrame>> {
+    fn lookup_ranges(
+        &self,
+        file_id: FileId,
+        addr: VirtAddr,
+        epoch: u64,
+    ) -> crate::Result<Vec<ResolvedFrame>> {
         let fid: u128 = file_id.into();
         let lower = RangeKey::new(fid, 0, 0);
         let upper = RangeKey::new(fid, addr, u16::MAX);
@@ -223,7 +684,7 @@ impl SymbolStore {
 
             if addr >= start && addr < end {
                 frames.push(ResolvedFrame {
-                    func: self.resolve_string(fid, val.func_ref())?,
+                    func: self.resolve_string(file_id, val.func_ref(), epoch)?,
                     depth: key.depth(),
                 });
             }
@@ -236,12 +697,25 @@ impl SymbolStore {
         Ok(frames)
     }
 
-    fn resolve_string(&self, file_id: u128, idx: u32) -> crate::Result<String> {
-        let key = StringKey::new(file_id, idx);
-        match self.strings.get(key.as_bytes())? {
-            Some(v) => Ok(String::from_utf8_lossy(&v).into_owned()),
-            None => Ok("[unknown]".into()),
+    fn resolve_string(&self, file_id: FileId, idx: u32, epoch: u64) -> crate::Result<String> {
+        if let Some(s) = self.cache.string(file_id, idx) {
+            return Ok(s);
         }
+        let key = StringKey::new(file_id.into(), idx);
+        let s = match self.strings.get(key.as_bytes())? {
+            Some(v) => String::from_utf8_lossy(&v).into_owned(),
+            None => "[unknown]".into(),
+        };
+        self.cache.insert_string(epoch, file_id, idx, &s);
+        Ok(s)
+    }
+
+    /// Whether symbols for `file_id` are already stored.
+    pub fn contains(&self, file_id: FileId) -> bool {
+        let fid_key = U128::<BigEndian>::new(file_id.into());
+        self.files
+            .get(fid_key.as_bytes())
+            .is_ok_and(|v| v.is_some())
     }
 
     /// Resolve a mapping basename to a stored FileId.
@@ -252,58 +726,610 @@ impl SymbolStore {
             .map(|base| base.get(basename).copied())?
     }
 
+    /// Translate a file offset within `file_id` into the ELF address its
+    /// symbols are keyed by. `None` for files stored without load segments.
+    pub fn elf_address(&self, file_id: FileId, file_offset: u64) -> Option<u64> {
+        if let Some(segments) = self.load_segments.read().unwrap().get(&file_id) {
+            return crate::symbolizer::elf_address(segments, file_offset);
+        }
+        let fid_key = U128::<BigEndian>::new(file_id.into());
+        let value = self.segments.get(fid_key.as_bytes()).ok()?;
+        // Files without segments are remembered too, so lookups falling
+        // back to raw addresses don't read the store every time.
+        let segments: Vec<LoadSegment> = value
+            .as_deref()
+            .and_then(|v| <[SegmentValue]>::ref_from_bytes(v).ok())
+            .unwrap_or_default()
+            .iter()
+            .map(|s| LoadSegment {
+                file_offset: s.file_offset.get(),
+                vaddr: s.vaddr.get(),
+                file_size: s.file_size.get(),
+            })
+            .collect();
+        let address = crate::symbolizer::elf_address(&segments, file_offset);
+        self.load_segments
+            .write()
+            .unwrap()
+            .insert(file_id, segments);
+        address
+    }
+
+    /// Stop resolving mappings by basename to a file whose source binary
+    /// at `path` changed; its symbols no longer match what is running.
+    pub fn mark_stale(&self, file_id: FileId, path: &Path) {
+        self.basename_index
+            .write()
+            .unwrap()
+            .retain(|_, v| *v != file_id);
+        self.stale
+            .write()
+            .unwrap()
+            .insert(path.to_path_buf(), file_id);
+    }
+
+    /// The stale file a new version loaded from `path` replaces, if any,
+    /// forgetting it.
+    pub fn take_stale(&self, path: &Path) -> Option<FileId> {
+        self.stale.write().unwrap().remove(path)
+    }
+
     /// List all stored executables.
     pub fn list_files(&self) -> crate::Result<Vec<ExecutableInfo>> {
         let mut result = Vec::new();
         for guard in self.files.range::<Vec<u8>, _>(..) {
             let (kb, vb) = guard.into_inner()?;
-            if let Some(info) = parse_file_meta(&kb, &vb) {
+            let stats = self.stats.get(&*kb)?;
+            let details = self.details.get(&*kb)?;
+            if let Some(mut info) = parse_file_meta(&kb, &vb, stats.as_deref(), details.as_deref())
+            {
+                // Lookups not flushed yet are newer than the stored time.
+                if let Some(&time) = self.last_lookup.read().unwrap().get(&info.file_id) {
+                    info.last_lookup = time;
+                }
                 result.push(info);
             }
         }
         Ok(result)
     }
 
-    /// Remove all stored symbols for a given file.
+    /// Remove all stored symbols for a given file, including the partial
+    /// data of an unfinished import. This is a single batch, so it holds a
+    /// removal for every stored entry of the file.
     pub fn remove_file_symbols(&self, file_id: FileId) -> crate::Result<()> {
         let fid: u128 = file_id.into();
         let prefix = U128::<BigEndian>::new(fid);
         let prefix_bytes = prefix.as_bytes();
+        let staged = U128::<BigEndian>::new(staging_key(fid));
 
         let mut batch = self.db.batch();
 
-        for guard in self.ranges.prefix(prefix_bytes) {
-            batch.remove(&self.ranges, guard.key()?);
-        }
-        for guard in self.strings.prefix(prefix_bytes) {
-            batch.remove(&self.strings, guard.key()?);
+        for key in [prefix_bytes, staged.as_bytes()] {
+            for guard in self.ranges.prefix(key) {
+                batch.remove(&self.ranges, guard.key()?);
+            }
+            for guard in self.strings.prefix(key) {
+                batch.remove(&self.strings, guard.key()?);
+            }
         }
         batch.remove(&self.files, prefix_bytes);
+        batch.remove(&self.stats, prefix_bytes);
+        batch.remove(&self.details, prefix_bytes);
+        batch.remove(&self.segments, prefix_bytes);
+        batch.remove(&self.pending, prefix_bytes);
+        batch.remove(&self.pending, staged.as_bytes());
         batch.commit()?;
+        self.load_segments.write().unwrap().remove(&file_id);
+        self.importing.write().unwrap().remove(&file_id);
+        self.cache.invalidate(file_id);
 
         self.basename_index
             .write()
             .unwrap()
             .retain(|_, v| *v != file_id);
+        self.last_lookup.write().unwrap().remove(&file_id);
+
+        Ok(())
+    }
+}
 
+/// An import in progress, written in chunks by [`SymbolImport::write_chunk`].
+///
+/// Dropping it without calling [`SymbolImport::finish`] leaves the pending
+/// marker behind, so the import can be resumed later or discarded with
+/// [`SymbolStore::discard_import`].
+pub struct SymbolImport<'a> {
+    store: &'a SymbolStore,
+    file_id: FileId,
+    /// Key prefix chunks are written under: the file's own, or its
+    /// [`staging_key`] when it replaces a stored version.
+    key: u128,
+    path: PathBuf,
+    /// Ranges committed by an interrupted earlier import.
+    resume_ranges: u64,
+    /// Ranges seen so far, including skipped ones.
+    ranges: u64,
+    /// Number of strings committed (indices are dense).
+    strings: u32,
+    size_bytes: u64,
+    /// [`PendingImport`] phase an interrupted earlier import reached.
+    phase: u8,
+}
+
+impl SymbolImport<'_> {
+    /// Commit one chunk together with the updated pending marker.
+    pub fn write_chunk(&mut self, chunk: &SymChunk) -> crate::Result<()> {
+        let store = self.store;
+        let fid = self.key;
+        let mut batch = store.db.batch();
+
+        for (i, s) in chunk.strings.iter().enumerate() {
+            let idx = chunk.first_string + i as u32;
+            if idx < self.strings {
+                continue;
+            }
+            let key = StringKey::new(fid, idx);
+            self.size_bytes += (key.as_bytes().len() + s.len()) as u64;
+            batch.insert(&store.strings, key.as_bytes(), s.as_bytes());
+            self.strings = idx + 1;
+        }
+
+        for r in &chunk.ranges {
+            self.ranges += 1;
+            if self.ranges <= self.resume_ranges {
+                continue;
+            }
+            let key = RangeKey::new(fid, r.va_start, r.depth);
+            let val = RangeValue::from_range(r);
+            self.size_bytes += (key.as_bytes().len() + val.as_bytes().len()) as u64;
+            batch.insert(&store.ranges, key.as_bytes(), val.as_bytes());
+        }
+
+        let fid_key = U128::<BigEndian>::new(fid);
+        batch.insert(
+            &store.pending,
+            fid_key.as_bytes(),
+            PendingImport::encode(
+                self.ranges.max(self.resume_ranges),
+                self.strings,
+                self.size_bytes,
+                self.phase,
+                &self.path,
+            ),
+        );
+        batch.commit()?;
         Ok(())
     }
+
+    /// Write file metadata and provenance and drop the pending marker,
+    /// making the file (or its new version) visible to lookups.
+    ///
+    /// A new version is swapped in over several batches of [`BATCH_KEYS`]
+    /// entries, first removing the previous symbols and then moving the
+    /// staged ones into place; the file is invisible to lookups meanwhile.
+    /// The pending marker records the phase, so an interrupted swap is
+    /// finished when the import is resumed.
+    pub fn finish(self, source: &SymSource) -> crate::Result<ExecutableInfo> {
+        let store = self.store;
+        let fid_key = U128::<BigEndian>::new(self.file_id.into());
+        let file_name = self
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let num_ranges = self.ranges.max(self.resume_ranges) as u32;
+        let mut meta_val = num_ranges.to_be_bytes().to_vec();
+        meta_val.extend_from_slice(file_name.as_bytes());
+
+        let now = unix_now();
+        let details = FileDetails {
+            path: self.path.to_string_lossy().into_owned(),
+            build_id: source
+                .build_id
+                .as_ref()
+                .map(|id| id.iter().map(|b| format!("{b:02x}")).collect()),
+            arch: source.arch.clone(),
+            file_size: source.file_size,
+            mtime: source.mtime,
+            extractors: source.extractors.iter().map(|e| e.to_string()).collect(),
+            imported_at: now,
+        };
+        let details_val = details.encode();
+        let segments_val: Vec<u8> = source
+            .segments
+            .iter()
+            .flat_map(|s| {
+                SegmentValue {
+                    file_offset: U64::new(s.file_offset),
+                    vaddr: U64::new(s.vaddr),
+                    file_size: U64::new(s.file_size),
+                }
+                .as_bytes()
+                .to_vec()
+            })
+            .collect();
+        let size_bytes = self.size_bytes
+            + (3 * fid_key.as_bytes().len()
+                + meta_val.len()
+                + details_val.len()
+                + segments_val.len()) as u64;
+
+        let staged = U128::<BigEndian>::new(self.key);
+        if self.key != fid_key.get() {
+            store.importing.write().unwrap().insert(self.file_id);
+            store.cache.invalidate(self.file_id);
+            let marker = |phase| {
+                let ranges = self.ranges.max(self.resume_ranges);
+                PendingImport::encode(ranges, self.strings, self.size_bytes, phase, &self.path)
+            };
+            if self.phase != PendingImport::MOVING_STAGED {
+                let removing = marker(PendingImport::REMOVING_OLD);
+                store.pending.insert(staged.as_bytes(), removing)?;
+                store.move_prefix(&store.ranges, fid_key.as_bytes(), None)?;
+                store.move_prefix(&store.strings, fid_key.as_bytes(), None)?;
+                let moving = marker(PendingImport::MOVING_STAGED);
+                store.pending.insert(staged.as_bytes(), moving)?;
+            }
+            for keyspace in [&store.ranges, &store.strings] {
+                store.move_prefix(keyspace, staged.as_bytes(), Some(fid_key.as_bytes()))?;
+            }
+        }
+        let mut batch = store.db.batch();
+        batch.insert(&store.files, fid_key.as_bytes(), &meta_val);
+        batch.insert(&store.details, fid_key.as_bytes(), &details_val);
+        batch.insert(&store.segments, fid_key.as_bytes(), &segments_val);
+        batch.insert(
+            &store.stats,
+            fid_key.as_bytes(),
+            FileStats::new(size_bytes, now).as_bytes(),
+        );
+        batch.remove(&store.pending, staged.as_bytes());
+        batch.commit()?;
+
+        store.importing.write().unwrap().remove(&self.file_id);
+        store.load_segments.write().unwrap().remove(&self.file_id);
+        store.cache.invalidate(self.file_id);
+        store
+            .basename_index
+            .write()
+            .unwrap()
+            .insert(basename_of(&file_name), self.file_id);
+        store.last_lookup.write().unwrap().insert(self.file_id, now);
+
+        Ok(ExecutableInfo {
+            file_id: self.file_id,
+            file_name,
+            num_ranges,
+            size_bytes,
+            last_lookup: now,
+            details: Some(details),
+        })
+    }
 }
 
-fn parse_file_meta(kb: &[u8], vb: &[u8]) -> Option<ExecutableInfo> {
+fn parse_file_meta(
+    kb: &[u8],
+    vb: &[u8],
+    stats: Option<&[u8]>,
+    details: Option<&[u8]>,
+) -> Option<ExecutableInfo> {
     let fid_key = U128::<BigEndian>::ref_from_bytes(kb).ok()?;
     if vb.len() < 4 {
         return None;
     }
     let num_ranges = u32::from_be_bytes(vb[..4].try_into().ok()?);
     let file_name = String::from_utf8_lossy(&vb[4..]).into_owned();
+    let stats = stats.and_then(|b| FileStats::ref_from_bytes(b).ok());
     Some(ExecutableInfo {
         file_id: FileId::from(fid_key.get()),
         file_name,
         num_ranges,
+        size_bytes: stats.map_or(0, |s| s.size_bytes.get()),
+        last_lookup: stats.map_or(0, |s| s.last_lookup.get()),
+        details: details.and_then(FileDetails::decode),
     })
 }
 
+/// Key prefix a re-import of the stored file `fid` is staged under.
+fn staging_key(fid: u128) -> u128 {
+    !fid
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
 fn basename_of(path: &str) -> String {
     path.rsplit('/').next().unwrap_or(path).to_owned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbolizer::StringRef;
+
+    fn chunk(num_ranges: u64) -> SymChunk {
+        SymChunk {
+            first_string: 0,
+            strings: vec!["func".to_string()],
+            ranges: (0..num_ranges)
+                .map(|i| SymRange {
+                    va_start: i * 0x10,
+                    length: 0x10,
+                    func: StringRef(0),
+                    file: None,
+                    call_file: None,
+                    call_line: None,
+                    depth: 0,
+                })
+                .collect(),
+        }
+    }
+
+    fn import(store: &SymbolStore, id: u128, num_ranges: u64, path: &str) -> ExecutableInfo {
+        let mut import = store
+            .begin_import(FileId::from(id), Path::new(path))
+            .unwrap();
+        import.write_chunk(&chunk(num_ranges)).unwrap();
+        import.finish(&SymSource::default()).unwrap()
+    }
+
+    #[test]
+    fn store_accounts_bytes_per_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SymbolStore::open(tmp.path()).unwrap();
+
+        let small = import(&store, 1, 1, "/bin/small");
+        let large = import(&store, 2, 100, "/bin/large");
+
+        assert!(large.size_bytes > small.size_bytes);
+        assert_eq!(
+            store.usage().total_bytes,
+            small.size_bytes + large.size_bytes
+        );
+    }
+
+    #[test]
+    fn quota_evicts_least_recently_used() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SymbolStore::open(tmp.path()).unwrap();
+
+        let old = import(&store, 1, 10, "/bin/old");
+        let used = import(&store, 2, 10, "/bin/used");
+        let fresh = import(&store, 3, 10, "/bin/fresh");
+
+        // Pretend `old` was last used long ago.
+        store.last_lookup.write().unwrap().insert(old.file_id, 1);
+
+        let store = store.with_quota(Some(used.size_bytes + fresh.size_bytes));
+        let evicted = store.enforce_quota(fresh.file_id).unwrap();
+
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].file_id, old.file_id);
+        assert!(store.file_id_for_basename("old").is_none());
+        assert!(store.file_id_for_basename("used").is_some());
+    }
+
+    #[test]
+    fn lookup_cache_hits_and_invalidates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SymbolStore::open(tmp.path()).unwrap();
+        let info = import(&store, 1, 4, "/bin/cached");
+
+        let first = store.lookup(info.file_id, 0x18).unwrap();
+        let second = store.lookup(info.file_id, 0x18).unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(second[0].func, "func");
+
+        let stats = store.usage().cache;
+        assert_eq!(stats.frame_hits, 1);
+        assert_eq!(stats.frame_misses, 1);
+
+        store.remove_file_symbols(info.file_id).unwrap();
+        assert!(store.lookup(info.file_id, 0x18).unwrap().is_empty());
+        assert_eq!(store.usage().cache.frame_misses, 2);
+    }
+
+    #[test]
+    fn lookup_times_are_flushed_to_stats() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file_id = FileId::from(1);
+        {
+            let store = SymbolStore::open(tmp.path()).unwrap();
+            let info = import(&store, 1, 4, "/bin/used");
+            // Pretend the file was last used long ago.
+            let fid_key = U128::<BigEndian>::new(1);
+            store
+                .stats
+                .insert(
+                    fid_key.as_bytes(),
+                    FileStats::new(info.size_bytes, 1).as_bytes(),
+                )
+                .unwrap();
+
+            store.lookup(file_id, 0x18).unwrap();
+            assert!(store.list_files().unwrap()[0].last_lookup > 1);
+        }
+
+        // Without a flush the lookup is only known in memory.
+        {
+            let store = SymbolStore::open(tmp.path()).unwrap();
+            assert_eq!(store.list_files().unwrap()[0].last_lookup, 1);
+
+            store.lookup(file_id, 0x18).unwrap();
+            store.flush_lookups().unwrap();
+        }
+
+        let store = SymbolStore::open(tmp.path()).unwrap();
+        assert!(store.list_files().unwrap()[0].last_lookup > 1);
+    }
+
+    #[test]
+    fn quota_never_evicts_kept_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SymbolStore::open(tmp.path()).unwrap().with_quota(Some(1));
+
+        let info = import(&store, 1, 10, "/bin/only");
+
+        assert!(store.enforce_quota(info.file_id).unwrap().is_empty());
+        assert_eq!(store.list_files().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn pending_import_is_invisible_until_finished() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file_id = FileId::from(1);
+        {
+            let store = SymbolStore::open(tmp.path()).unwrap();
+            let mut import = store.begin_import(file_id, Path::new("/bin/big")).unwrap();
+            import.write_chunk(&chunk(4)).unwrap();
+            assert!(store.lookup(file_id, 0x18).unwrap().is_empty());
+            assert!(!store.contains(file_id));
+        }
+
+        // Reopening resumes after the committed chunk.
+        let store = SymbolStore::open(tmp.path()).unwrap();
+        assert!(store.lookup(file_id, 0x18).unwrap().is_empty());
+        let pending = store.pending_imports().unwrap();
+        assert_eq!(pending, vec![(file_id, PathBuf::from("/bin/big"))]);
+
+        let mut import = store.begin_import(file_id, Path::new("/bin/big")).unwrap();
+        import.write_chunk(&chunk(6)).unwrap();
+        let info = import.finish(&SymSource::default()).unwrap();
+
+        assert_eq!(info.num_ranges, 6);
+        assert_eq!(store.lookup(file_id, 0x58).unwrap()[0].func, "func");
+        assert!(store.pending_imports().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reimport_keeps_previous_symbols_until_finished() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SymbolStore::open(tmp.path()).unwrap();
+        let info = import(&store, 1, 4, "/bin/app");
+
+        // A discarded re-import leaves the stored version untouched.
+        let mut reimport = store
+            .begin_import(info.file_id, Path::new("/bin/app"))
+            .unwrap();
+        reimport.write_chunk(&chunk(2)).unwrap();
+        assert_eq!(store.lookup(info.file_id, 0x38).unwrap().len(), 1);
+        drop(reimport);
+        store.discard_import(info.file_id).unwrap();
+        assert_eq!(store.lookup(info.file_id, 0x38).unwrap().len(), 1);
+        assert!(store.pending_imports().unwrap().is_empty());
+
+        // A finished one replaces it.
+        let replaced = import(&store, 1, 2, "/bin/app");
+        assert_eq!(replaced.num_ranges, 2);
+        assert!(store.lookup(info.file_id, 0x38).unwrap().is_empty());
+        assert_eq!(store.lookup(info.file_id, 0x18).unwrap().len(), 1);
+        assert_eq!(store.usage().total_bytes, replaced.size_bytes);
+    }
+
+    #[test]
+    fn interrupted_swap_is_finished_on_resume() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file_id = FileId::from(1);
+        let path = Path::new("/bin/app");
+        {
+            let store = SymbolStore::open(tmp.path()).unwrap();
+            import(&store, 1, 4, "/bin/app");
+            let mut reimport = store.begin_import(file_id, path).unwrap();
+            reimport.write_chunk(&chunk(6)).unwrap();
+            drop(reimport);
+
+            // Stop as if the process died once the old symbols were gone.
+            let fid_key = U128::<BigEndian>::new(1);
+            let staged = U128::<BigEndian>::new(staging_key(1));
+            let marker = store.pending.get(staged.as_bytes()).unwrap().unwrap();
+            let (marker, _) = PendingImport::decode(&marker).unwrap();
+            let (ranges, strings) = (marker.ranges.get(), marker.strings.get());
+            let size_bytes = marker.size_bytes.get();
+            for keyspace in [&store.ranges, &store.strings] {
+                store
+                    .move_prefix(keyspace, fid_key.as_bytes(), None)
+                    .unwrap();
+            }
+            let phase = PendingImport::MOVING_STAGED;
+            let marker = PendingImport::encode(ranges, strings, size_bytes, phase, path);
+            store.pending.insert(staged.as_bytes(), marker).unwrap();
+        }
+
+        // The half-swapped file stays hidden until the import is resumed.
+        let store = SymbolStore::open(tmp.path()).unwrap();
+        assert!(store.lookup(file_id, 0x18).unwrap().is_empty());
+        assert_eq!(
+            store.pending_imports().unwrap(),
+            vec![(file_id, path.to_path_buf())]
+        );
+
+        let mut reimport = store.begin_import(file_id, path).unwrap();
+        reimport.write_chunk(&chunk(6)).unwrap();
+        let info = reimport.finish(&SymSource::default()).unwrap();
+
+        assert_eq!(info.num_ranges, 6);
+        assert_eq!(store.lookup(file_id, 0x58).unwrap()[0].func, "func");
+        assert!(store.pending_imports().unwrap().is_empty());
+    }
+
+    #[test]
+    fn details_round_trip_through_list_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SymbolStore::open(tmp.path()).unwrap();
+        let source = SymSource {
+            build_id: Some(vec![0xab, 0x01]),
+            arch: "x86_64".to_string(),
+            file_size: 4096,
+            mtime: 1_700_000_000,
+            extractors: vec!["dwarf", "dyn-obj-sym"],
+            segments: vec![LoadSegment {
+                file_offset: 0x1000,
+                vaddr: 0x2000,
+                file_size: 0x1000,
+            }],
+        };
+        let mut import = store
+            .begin_import(FileId::from(1), Path::new("/usr/bin/app"))
+            .unwrap();
+        import.write_chunk(&chunk(2)).unwrap();
+        import.finish(&source).unwrap();
+
+        let files = store.list_files().unwrap();
+        let details = files[0].details.as_ref().unwrap();
+        assert_eq!(details.path, "/usr/bin/app");
+        assert_eq!(details.build_id.as_deref(), Some("ab01"));
+        assert_eq!(details.arch, "x86_64");
+        assert_eq!(details.file_size, 4096);
+        assert_eq!(details.mtime, 1_700_000_000);
+        assert_eq!(details.extractors, ["dwarf", "dyn-obj-sym"]);
+        assert!(details.imported_at > 0);
+        assert_eq!(store.elf_address(FileId::from(1), 0x1010), Some(0x2010));
+        assert_eq!(store.elf_address(FileId::from(1), 0x3000), None);
+    }
+
+    #[test]
+    fn elf_address_needs_stored_segments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SymbolStore::open(tmp.path()).unwrap();
+        import(&store, 1, 2, "/usr/bin/old");
+        assert_eq!(store.elf_address(FileId::from(1), 0x1010), None);
+        assert_eq!(store.elf_address(FileId::from(2), 0x1010), None);
+    }
+}
@@ -1,15 +1,35 @@
-use indexmap::IndexSet;
-use std::path::Path;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use object::{Object, ObjectSegment, ReadCache};
 use symblib::symbconv::RangeExtractor;
 use symblib::{VirtAddr, symbconv};
 
+/// Ranges handed to the sink per chunk; bounds memory use on huge binaries.
+pub const CHUNK_RANGES: usize = 64 * 1024;
+
+/// Ranges between calls of the progress callback of [`extract_symbols`].
+const PROGRESS_RANGES: usize = 4 * 1024;
+
+/// Bytes of strings remembered for deduplication across chunks. Once
+/// exceeded the table starts over, so strings seen before are stored again.
+const INTERN_BYTES: usize = 32 * 1024 * 1024;
+
 #[derive(Debug, Clone, Copy)]
 pub struct StringRef(pub u32);
 
-pub struct FileSym {
-    pub file_id: symblib::fileid::FileId,
+/// A batch of extracted ranges together with the strings first referenced
+/// in it. String indices are dense across the whole extraction, so
+/// `strings[i]` has index `first_string + i`. A string may come again in a
+/// later chunk under a new index, see [`INTERN_BYTES`].
+#[derive(Default)]
+pub struct SymChunk {
+    pub first_string: u32,
+    pub strings: Vec<String>,
     pub ranges: Vec<SymRange>,
-    pub strings: IndexSet<String>,
 }
 
 pub struct SymRange {
@@ -22,51 +42,286 @@ pub struct SymRange {
     pub depth: u16,
 }
 
-pub fn extract_symbols(path: &Path) -> crate::Result<FileSym> {
-    let file_id = symblib::fileid::FileId::from_path(path)?;
+/// A `PT_LOAD` segment: file bytes `[file_offset, file_offset + file_size)`
+/// are mapped at ELF virtual address `vaddr`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadSegment {
+    pub file_offset: u64,
+    pub vaddr: u64,
+    pub file_size: u64,
+}
+
+/// Translate a file offset into the ELF virtual address symbols are keyed
+/// by, through the load segment containing it.
+pub fn elf_address(segments: &[LoadSegment], file_offset: u64) -> Option<u64> {
+    segments
+        .iter()
+        .find(|s| file_offset >= s.file_offset && file_offset - s.file_offset < s.file_size)
+        .map(|s| file_offset - s.file_offset + s.vaddr)
+}
+
+/// Where a file's symbols came from, recorded next to them in the store.
+#[derive(Clone, Default)]
+pub struct SymSource {
+    pub build_id: Option<Vec<u8>>,
+    pub arch: String,
+    pub file_size: u64,
+    /// Modification time of the file, in seconds since the epoch.
+    pub mtime: u64,
+    /// Extractors that contributed ranges, in priority order.
+    pub extractors: Vec<&'static str>,
+    pub segments: Vec<LoadSegment>,
+}
+
+/// Interns strings across chunks, within [`INTERN_BYTES`], and collects
+/// the current chunk.
+#[derive(Default)]
+struct ChunkBuilder {
+    interned: HashMap<String, u32>,
+    interned_bytes: usize,
+    /// Index of the next new string.
+    next: u32,
+    chunk: SymChunk,
+}
+
+impl ChunkBuilder {
+    fn intern(&mut self, s: String) -> StringRef {
+        if let Some(&idx) = self.interned.get(&s) {
+            return StringRef(idx);
+        }
+        if self.interned_bytes + s.len() > INTERN_BYTES {
+            self.interned.clear();
+            self.interned_bytes = 0;
+        }
+        let idx = self.next;
+        self.next += 1;
+        self.interned_bytes += s.len();
+        self.chunk.strings.push(s.clone());
+        self.interned.insert(s, idx);
+        StringRef(idx)
+    }
+
+    fn take(&mut self) -> SymChunk {
+        let first_string = self.next;
+        std::mem::replace(
+            &mut self.chunk,
+            SymChunk {
+                first_string,
+                ..Default::default()
+            },
+        )
+    }
+}
+
+/// An extractor added to the multi extractor, marking itself as the one
+/// ranges reaching the sink come from while it runs.
+struct Attributed<'a, E> {
+    index: usize,
+    active: &'a Cell<usize>,
+    inner: E,
+}
+
+impl<E: RangeExtractor> RangeExtractor for Attributed<'_, E> {
+    fn extract(
+        &self,
+        visitor: &mut dyn FnMut(symbconv::Range) -> symbconv::Result<()>,
+    ) -> symbconv::Result<()> {
+        self.active.set(self.index);
+        self.inner.extract(visitor)
+    }
+}
+
+/// Add `extractor` to `multi` under `name`, recording the name at the
+/// index it is attributed by.
+fn add_extractor<'a>(
+    multi: &mut symbconv::multi::Extractor<'a>,
+    names: &mut Vec<&'static str>,
+    active: &'a Cell<usize>,
+    name: &'static str,
+    extractor: impl RangeExtractor + 'a,
+) {
+    let index = names.len();
+    names.push(name);
+    multi.add(
+        name,
+        Attributed {
+            index,
+            active,
+            inner: extractor,
+        },
+    );
+}
+
+/// Extract symbol ranges for `path`, streaming them to `sink` in chunks of
+/// up to [`CHUNK_RANGES`] ranges.
+///
+/// Memory held here is bounded by one chunk and [`INTERN_BYTES`] of
+/// strings. The object and debug sections symblib loads, and whatever
+/// state its extractors keep while walking them, are not bounded by this.
+///
+/// If the binary has no DWARF of its own, its separate debug file is looked
+/// up in `debug_dirs` (see [`crate::debugfile::locate`]) and its ranges are
+/// merged in. Chunks come in extraction order, which is deterministic for a
+/// given file but not sorted by address.
+///
+/// `progress` is called with the number of ranges extracted so far every
+/// [`PROGRESS_RANGES`] ranges, well before the first chunk is complete;
+/// returning `false` cancels the extraction with
+/// [`crate::error::Error::Cancelled`]. An error from `sink` stops it and is
+/// returned as is. Either way the extractors are unwound right away.
+pub fn extract_symbols(
+    path: &Path,
+    debug_dirs: &[PathBuf],
+    progress: &mut dyn FnMut(usize) -> bool,
+    sink: &mut dyn FnMut(SymChunk) -> crate::Result<()>,
+) -> crate::Result<SymSource> {
     let obj = symblib::objfile::File::load(path)?;
     let obj = obj.parse()?;
-    let dwarf_secs = symblib::dwarf::Sections::load(&obj)?;
 
+    let debug_path = crate::debugfile::locate(path, debug_dirs);
+    let mut source = inspect(path);
+    let debug_obj = debug_path
+        .map(|debug_path| symblib::objfile::File::load(&debug_path))
+        .transpose()?;
+    let debug_obj = debug_obj.as_ref().map(|o| o.parse()).transpose()?;
+    let dwarf_secs = symblib::dwarf::Sections::load(debug_obj.as_ref().unwrap_or(&obj))?;
+
+    let active = Cell::new(0);
+    let mut names = Vec::new();
     let mut multi_extractor = symbconv::multi::Extractor::new(&obj)?;
-    multi_extractor.add("dwarf", symbconv::dwarf::Extractor::new(&dwarf_secs));
-    multi_extractor.add("go", symbconv::go::Extractor::new(&obj));
-    multi_extractor.add(
-        "dbg-obj-sym",
-        symbconv::obj::Extractor::new(&obj, symblib::objfile::SymbolSource::Debug),
-    );
-    multi_extractor.add(
-        "dyn-obj-sym",
-        symbconv::obj::Extractor::new(&obj, symblib::objfile::SymbolSource::Dynamic),
-    );
+    let multi = &mut multi_extractor;
+    let dwarf = symbconv::dwarf::Extractor::new(&dwarf_secs);
+    add_extractor(multi, &mut names, &active, "dwarf", dwarf);
+    let go = symbconv::go::Extractor::new(&obj);
+    add_extractor(multi, &mut names, &active, "go", go);
+    if let Some(debug_obj) = &debug_obj {
+        let debug_syms =
+            symbconv::obj::Extractor::new(debug_obj, symblib::objfile::SymbolSource::Debug);
+        add_extractor(multi, &mut names, &active, "debug-file-sym", debug_syms);
+    }
+    let debug_syms = symbconv::obj::Extractor::new(&obj, symblib::objfile::SymbolSource::Debug);
+    add_extractor(multi, &mut names, &active, "dbg-obj-sym", debug_syms);
+    let dyn_syms = symbconv::obj::Extractor::new(&obj, symblib::objfile::SymbolSource::Dynamic);
+    add_extractor(multi, &mut names, &active, "dyn-obj-sym", dyn_syms);
 
-    let mut strings = IndexSet::with_capacity(1024);
-    let mut ranges = Vec::with_capacity(1024);
-    multi_extractor.extract(&mut |range| {
-        let (func_idx, _) = strings.insert_full(range.func);
-        ranges.push(SymRange {
+    let mut emitted = vec![false; names.len()];
+    let mut builder = ChunkBuilder::default();
+    let mut extracted = 0;
+    // Why the visitor stopped the extractors, reported instead of the
+    // error they unwind with.
+    let mut stopped = None;
+    let stop = |err, stopped: &mut Option<crate::error::Error>| {
+        *stopped = Some(err);
+        Err(stopped_early())
+    };
+    let result = multi_extractor.extract(&mut |range| {
+        if stopped.is_some() {
+            return Err(stopped_early());
+        }
+        emitted[active.get()] = true;
+        let func = builder.intern(range.func);
+        let file = range.file.map(|f| builder.intern(f));
+        let call_file = range.call_file.map(|cf| builder.intern(cf));
+        builder.chunk.ranges.push(SymRange {
             va_start: range.elf_va,
             length: range.length,
-            func: StringRef(func_idx as u32),
-            file: range.file.map(|f| {
-                let (i, _) = strings.insert_full(f);
-                StringRef(i as u32)
-            }),
-            call_file: range.call_file.map(|cf| {
-                let (i, _) = strings.insert_full(cf);
-                StringRef(i as u32)
-            }),
+            func,
+            file,
+            call_file,
             call_line: range.call_line,
             depth: range.depth as u16,
         });
+        extracted += 1;
+        if extracted % PROGRESS_RANGES == 0 && !progress(extracted) {
+            return stop(crate::error::Error::Cancelled, &mut stopped);
+        }
+        if builder.chunk.ranges.len() >= CHUNK_RANGES
+            && let Err(err) = sink(builder.take())
+        {
+            return stop(err, &mut stopped);
+        }
         Ok(())
-    })?;
+    });
+    if let Some(err) = stopped {
+        return Err(err);
+    }
+    result?;
+
+    if !progress(extracted) {
+        return Err(crate::error::Error::Cancelled);
+    }
+    sink(builder.take())?;
+    source.extractors = names
+        .into_iter()
+        .zip(emitted)
+        .filter_map(|(name, emitted)| emitted.then_some(name))
+        .collect();
+    Ok(source)
+}
+
+/// Error the visitor returns to make the extractors unwind.
+fn stopped_early() -> symbconv::Error {
+    symbconv::Error::Visitor("extraction stopped".into())
+}
+
+/// Read file metadata: size, modification time, build ID, architecture and
+/// load segments. Extractors are filled in once extraction ran.
+fn inspect(path: &Path) -> SymSource {
+    let mut source = SymSource::default();
+    if let Ok(meta) = std::fs::metadata(path) {
+        source.file_size = meta.len();
+        source.mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+    }
+
+    let Ok(file) = File::open(path) else {
+        return source;
+    };
+    let cache = ReadCache::new(file);
+    let Ok(obj) = object::File::parse(&cache) else {
+        return source;
+    };
+    source.build_id = obj.build_id().ok().flatten().map(<[u8]>::to_vec);
+    source.arch = format!("{:?}", obj.architecture()).to_lowercase();
+    source.segments = obj
+        .segments()
+        .map(|seg| {
+            let (file_offset, file_size) = seg.file_range();
+            LoadSegment {
+                file_offset,
+                vaddr: seg.address(),
+                file_size,
+            }
+        })
+        .collect();
+
+    source
+}
 
-    ranges.sort_unstable_by(|a, b| a.va_start.cmp(&b.va_start).then(a.depth.cmp(&b.depth)));
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(FileSym {
-        file_id,
-        ranges,
-        strings,
-    })
+    #[test]
+    fn elf_address_applies_segment_bias() {
+        // Typical PIE layout: read-only segment at 0, text shifted by a page.
+        let segments = [
+            LoadSegment {
+                file_offset: 0,
+                vaddr: 0,
+                file_size: 0x1000,
+            },
+            LoadSegment {
+                file_offset: 0x1000,
+                vaddr: 0x2000,
+                file_size: 0x3000,
+            },
+        ];
+        assert_eq!(elf_address(&segments, 0x800), Some(0x800));
+        assert_eq!(elf_address(&segments, 0x1234), Some(0x2234));
+        assert_eq!(elf_address(&segments, 0x4000), None);
+    }
 }
@@ -10,26 +10,52 @@ use std::time::{Duration, Instant};
 
 use crate::error::Result;
 use crate::flamegraph::FlameGraph;
-use crate::storage::ExecutableInfo;
+use crate::grpc::DiscoveredMapping;
+use crate::jobs::{JobId, JobState};
+use crate::stale::StaleFile;
+use crate::storage::{ExecutableInfo, StoreUsage};
 
 pub enum Event {
     Tick,
     Key(KeyEvent),
     Resize,
     ProfileUpdate {
-        flamegraph: FlameGraph,
+        flamegraph: Box<FlameGraph>,
         samples: u64,
         timestamps: HashMap<String, Vec<u64>>,
     },
-    MappingsDiscovered(Vec<String>),
+    MappingsDiscovered(Vec<DiscoveredMapping>),
     SymbolsLoaded {
         target_name: String,
         info: Result<ExecutableInfo>,
     },
+    SymbolsEvicted(Result<Vec<ExecutableInfo>>),
+    /// Stored files whose source binary changed on disk.
+    SymbolsStale(Vec<StaleFile>),
+    JobQueued {
+        id: JobId,
+        name: String,
+    },
+    JobUpdate {
+        id: JobId,
+        state: JobState,
+    },
+    /// Mappings of a process were scanned: `(queued, already stored)`.
+    ProcessScanned {
+        pid: u32,
+        result: Result<(usize, usize)>,
+    },
     SymbolsRemoved {
         name: String,
         error: Option<crate::error::Error>,
     },
+    StoreCompacted(Option<crate::error::Error>),
+    /// Stored files, listed again when the executables tab is opened.
+    StoreListed(Result<Vec<ExecutableInfo>>),
+    /// Store disk usage, measured off the UI thread.
+    StoreUsage(StoreUsage),
+    /// Perf map files gained symbols since the last refresh.
+    PerfMapsChanged,
 }
 
 #[allow(dead_code)]
@@ -3,13 +3,52 @@ use std::path::{Path, PathBuf};
 use ratatui::crossterm::event::{KeyCode, KeyEvent};
 
 use super::Action;
-use crate::storage::{ExecutableInfo, FileId};
+use crate::jobs::{JobId, JobState};
+use crate::storage::{ExecutableInfo, FileDetails, FileId, StoreUsage};
 
 #[derive(Clone)]
 pub struct ExeEntry {
     pub name: String,
     pub file_id: Option<FileId>,
     pub num_ranges: Option<u32>,
+    pub size_bytes: Option<u64>,
+    pub last_lookup: Option<u64>,
+    pub details: Option<FileDetails>,
+    /// The source binary changed on disk since these symbols were loaded.
+    pub stale: bool,
+}
+
+impl ExeEntry {
+    fn discovered(name: String) -> Self {
+        Self {
+            name,
+            file_id: None,
+            num_ranges: None,
+            size_bytes: None,
+            last_lookup: None,
+            details: None,
+            stale: false,
+        }
+    }
+
+    fn set_info(&mut self, info: &ExecutableInfo) {
+        if self.file_id != Some(info.file_id) {
+            self.stale = false;
+        }
+        self.file_id = Some(info.file_id);
+        self.num_ranges = Some(info.num_ranges);
+        self.size_bytes = Some(info.size_bytes);
+        self.last_lookup = Some(info.last_lookup);
+        self.details = info.details.clone();
+    }
+}
+
+impl From<ExecutableInfo> for ExeEntry {
+    fn from(info: ExecutableInfo) -> Self {
+        let mut entry = Self::discovered(info.file_name.clone());
+        entry.set_info(&info);
+        entry
+    }
 }
 
 #[derive(Default)]
@@ -47,29 +86,126 @@ impl PathInput {
     }
 }
 
+#[derive(Default)]
+pub struct PidInput {
+    pub active: bool,
+    pub input: String,
+}
+
+impl PidInput {
+    pub fn open(&mut self) {
+        *self = Self {
+            active: true,
+            ..Default::default()
+        };
+    }
+
+    pub fn close(&mut self) {
+        *self = Self::default();
+    }
+}
+
+pub struct JobEntry {
+    pub id: JobId,
+    pub name: String,
+    pub state: JobState,
+}
+
+/// Symbol loads submitted this session, oldest first.
+#[derive(Default)]
+pub struct JobsPanel {
+    pub active: bool,
+    pub list: Vec<JobEntry>,
+    pub cursor: usize,
+    pub scroll: usize,
+}
+
+impl JobsPanel {
+    pub fn queued(&mut self, id: JobId, name: String) {
+        self.list.push(JobEntry {
+            id,
+            name,
+            state: JobState::Queued,
+        });
+    }
+
+    pub fn update(&mut self, id: JobId, state: JobState) {
+        if let Some(job) = self.list.iter_mut().find(|j| j.id == id) {
+            job.state = state;
+        }
+    }
+
+    pub fn name(&self, id: JobId) -> Option<&str> {
+        self.list
+            .iter()
+            .find(|j| j.id == id)
+            .map(|j| j.name.as_str())
+    }
+
+    /// Number of `(queued, running)` jobs.
+    pub fn pending(&self) -> (usize, usize) {
+        self.list
+            .iter()
+            .fold((0, 0), |(queued, running), job| match job.state {
+                JobState::Queued => (queued + 1, running),
+                JobState::Running { .. } => (queued, running + 1),
+                _ => (queued, running),
+            })
+    }
+
+    fn clear_finished(&mut self) {
+        self.list.retain(|j| !j.state.is_finished());
+        self.cursor = self.cursor.min(self.list.len().saturating_sub(1));
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('J') => self.active = false,
+            KeyCode::Down | KeyCode::Char('j') if self.cursor + 1 < self.list.len() => {
+                self.cursor += 1;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            KeyCode::Char('x') => {
+                if let Some(job) = self.list.get(self.cursor)
+                    && !job.state.is_finished()
+                {
+                    return Action::CancelJob(job.id);
+                }
+            }
+            KeyCode::Char('C') => self.clear_finished(),
+            _ => {}
+        };
+        Action::None
+    }
+}
+
 pub struct ExecutablesTab {
     pub cursor: usize,
     pub scroll: usize,
     pub list: Vec<ExeEntry>,
     pub status: Option<String>,
     pub path_input: PathInput,
+    pub pid_input: PidInput,
+    pub jobs: JobsPanel,
+    /// Whether the detail popup for the entry under the cursor is open.
+    pub detail: bool,
+    pub usage: StoreUsage,
 }
 
 impl From<Vec<ExecutableInfo>> for ExecutablesTab {
     fn from(exes: Vec<ExecutableInfo>) -> Self {
         Self {
-            list: exes
-                .into_iter()
-                .map(|info| ExeEntry {
-                    name: info.file_name,
-                    file_id: Some(info.file_id),
-                    num_ranges: Some(info.num_ranges),
-                })
-                .collect(),
+            list: exes.into_iter().map(ExeEntry::from).collect(),
             cursor: 0,
             scroll: 0,
             status: None,
             path_input: PathInput::default(),
+            pid_input: PidInput::default(),
+            jobs: JobsPanel::default(),
+            detail: false,
+            usage: StoreUsage::default(),
         }
     }
 }
@@ -78,11 +214,7 @@ impl ExecutablesTab {
     pub fn merge_discovered_mappings(&mut self, names: Vec<String>) {
         for name in names {
             if !self.list.iter().any(|e| e.name == name) {
-                self.list.push(ExeEntry {
-                    name,
-                    file_id: None,
-                    num_ranges: None,
-                });
+                self.list.push(ExeEntry::discovered(name));
             }
         }
         self.sort_list();
@@ -90,22 +222,39 @@ impl ExecutablesTab {
 
     pub fn update_symbolized(&mut self, target_name: String, info: ExecutableInfo) {
         if let Some(entry) = self.list.iter_mut().find(|e| e.name == target_name) {
-            entry.file_id = Some(info.file_id);
-            entry.num_ranges = Some(info.num_ranges);
+            entry.set_info(&info);
         } else {
-            self.list.push(ExeEntry {
-                name: info.file_name,
-                file_id: Some(info.file_id),
-                num_ranges: Some(info.num_ranges),
-            });
+            self.list.push(ExeEntry::from(info));
         }
         self.sort_list();
     }
 
     pub fn clear_symbols(&mut self, name: &str) {
         if let Some(entry) = self.list.iter_mut().find(|e| e.name == name) {
-            entry.file_id = None;
-            entry.num_ranges = None;
+            *entry = ExeEntry::discovered(entry.name.clone());
+        }
+        self.sort_list();
+    }
+
+    pub fn mark_stale(&mut self, file_ids: impl IntoIterator<Item = FileId>) {
+        for file_id in file_ids {
+            for entry in self.list.iter_mut().filter(|e| e.file_id == Some(file_id)) {
+                entry.stale = true;
+            }
+        }
+    }
+
+    /// Refresh per-file stats from the store.
+    pub fn sync_store(&mut self, infos: Vec<ExecutableInfo>) {
+        for info in infos {
+            match self
+                .list
+                .iter_mut()
+                .find(|e| e.file_id == Some(info.file_id))
+            {
+                Some(entry) => entry.set_info(&info),
+                None => self.list.push(ExeEntry::from(info)),
+            }
         }
         self.sort_list();
     }
@@ -140,11 +289,21 @@ impl ExecutablesTab {
         if self.path_input.active {
             return self.handle_path_input_key(key);
         }
+        if self.pid_input.active {
+            return self.handle_pid_input_key(key);
+        }
+        if self.jobs.active {
+            return self.jobs.handle_key(key);
+        }
+        if self.detail {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Char('i')) {
+                self.detail = false;
+            }
+            return Action::None;
+        }
         match key.code {
-            KeyCode::Down | KeyCode::Char('j') => {
-                if self.cursor + 1 < self.list.len() {
-                    self.cursor += 1;
-                }
+            KeyCode::Down | KeyCode::Char('j') if self.cursor + 1 < self.list.len() => {
+                self.cursor += 1;
             }
             KeyCode::Up | KeyCode::Char('k') => {
                 self.cursor = self.cursor.saturating_sub(1);
@@ -161,7 +320,35 @@ impl ExecutablesTab {
                     return Action::RemoveSymbols(entry.name.clone(), file_id);
                 }
             }
+            KeyCode::Char('c') => return Action::CompactStore,
             KeyCode::Char('/') => self.path_input.open(None),
+            KeyCode::Char('p') => self.pid_input.open(),
+            KeyCode::Char('J') => self.jobs.active = true,
+            KeyCode::Char('i') => self.detail = !self.list.is_empty(),
+            _ => {}
+        };
+        Action::None
+    }
+
+    fn handle_pid_input_key(&mut self, key: KeyEvent) -> Action {
+        match key.code {
+            KeyCode::Esc => self.pid_input.close(),
+            KeyCode::Enter => {
+                let input = self.pid_input.input.trim().to_string();
+                self.pid_input.close();
+                match input.parse::<u32>() {
+                    Ok(pid) => {
+                        self.status = Some(format!("Loading mappings of PID {}", pid));
+                        return Action::LoadProcess(pid);
+                    }
+                    Err(_) if input.is_empty() => {}
+                    Err(_) => self.status = Some(format!("Error: invalid PID `{}`", input)),
+                }
+            }
+            KeyCode::Backspace => {
+                self.pid_input.input.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() => self.pid_input.input.push(c),
             _ => {}
         };
         Action::None
@@ -190,10 +377,10 @@ impl ExecutablesTab {
                 self.path_input.completion_cursor =
                     self.path_input.completion_cursor.saturating_sub(1);
             }
-            KeyCode::Down => {
-                if self.path_input.completion_cursor + 1 < self.path_input.completions.len() {
-                    self.path_input.completion_cursor += 1;
-                }
+            KeyCode::Down
+                if self.path_input.completion_cursor + 1 < self.path_input.completions.len() =>
+            {
+                self.path_input.completion_cursor += 1;
             }
             KeyCode::Char(c) => {
                 self.path_input.input.push(c);
@@ -1,6 +1,17 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
 use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use regex::Regex;
 
-use crate::flamegraph::{FlameGraph, FlameNode, get_node, get_zoom_node};
+use crate::flamegraph::{
+    Diff, FlameGraph, FlameTree, FrameOrder, NameId, NativeFrame, NodeBudget, NodeId, Recursion,
+    Sandwich, StackFilter, ThreadView, get_node, get_zoom_node,
+};
+use crate::rules::FrameRules;
 
 #[derive(Default)]
 pub struct Selection {
@@ -9,13 +20,19 @@ pub struct Selection {
     pub total_value: i64,
     pub pct: f64,
     pub depth: usize,
+    /// The frame was inlined into its caller.
+    pub inlined: bool,
+    /// Baseline `(self, total)` of the selected frame while diffing.
+    pub baseline: Option<(i64, i64)>,
 }
 
 #[derive(Default)]
 pub struct SearchOverlay {
     pub active: bool,
     pub input: String,
-    pub matches: Vec<(String, usize)>,
+    /// Matching top-level frames, kept by name so they stay valid while
+    /// the tree changes underneath.
+    pub matches: Vec<(String, NameId)>,
     pub cursor: usize,
 }
 
@@ -32,6 +49,88 @@ impl SearchOverlay {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    Focus,
+    Ignore,
+}
+
+impl FilterKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Focus => "focus",
+            Self::Ignore => "ignore",
+        }
+    }
+}
+
+/// Regex being typed for the focus or ignore filter.
+pub struct FilterPrompt {
+    pub kind: FilterKind,
+    pub input: String,
+}
+
+/// How much recent data the windowed mode keeps.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WindowSpan {
+    Duration(Duration),
+    Exports(usize),
+}
+
+impl WindowSpan {
+    pub const DEFAULT: Self = Self::Duration(Duration::from_secs(60));
+
+    pub fn label(self) -> String {
+        match self {
+            Self::Duration(d) => format!("last {}s", d.as_secs()),
+            Self::Exports(n) => format!("last {n} exports"),
+        }
+    }
+}
+
+/// Received samples bucketed by arrival time, so the oldest can expire.
+/// Buckets hold bare trees in the names of the tab's graph.
+struct Window {
+    span: WindowSpan,
+    buckets: VecDeque<(Instant, FlameTree)>,
+}
+
+impl Window {
+    /// Time-based windows use about 60 buckets, at least a second wide.
+    fn push(&mut self, now: Instant, samples: FlameTree) {
+        if let WindowSpan::Duration(span) = self.span
+            && let Some((start, bucket)) = self.buckets.back_mut()
+            && now.duration_since(*start) < (span / 60).max(Duration::from_secs(1))
+        {
+            bucket.merge_from(&samples, |id| id);
+            return;
+        }
+        self.buckets.push_back((now, samples));
+    }
+
+    /// Drop buckets that fell out of the window; true if any did.
+    fn expire(&mut self, now: Instant) -> bool {
+        let before = self.buckets.len();
+        match self.span {
+            WindowSpan::Duration(span) => {
+                while self
+                    .buckets
+                    .front()
+                    .is_some_and(|(start, _)| now.duration_since(*start) > span)
+                {
+                    self.buckets.pop_front();
+                }
+            }
+            WindowSpan::Exports(n) => {
+                while self.buckets.len() > n {
+                    self.buckets.pop_front();
+                }
+            }
+        }
+        self.buckets.len() != before
+    }
+}
+
 pub struct FlamegraphTab {
     pub graph: FlameGraph,
     pub frozen: bool,
@@ -39,9 +138,38 @@ pub struct FlamegraphTab {
     pub samples_received: u64,
     pub scroll_y: usize,
     pub cursor_path: Vec<usize>,
-    pub zoom_path: Vec<String>,
+    pub zoom_path: Vec<NameId>,
     pub selection: Selection,
     pub search: SearchOverlay,
+    /// Bottom-up view of `graph.root`, present while the view is inverted.
+    pub inverted: Option<FlameTree>,
+    /// Graph the current one is compared against in diff mode.
+    pub baseline: Option<FlameGraph>,
+    /// Baseline values for the displayed tree, present while diffing.
+    pub diff: Option<Diff>,
+    /// Outcome of the last capture or export, shown until the next key.
+    pub notice: Option<String>,
+    /// Callers and callees of the function picked with `s`, shown instead
+    /// of the graph while present.
+    pub sandwich: Option<Sandwich>,
+    /// Bumped whenever the displayed graph changes.
+    pub generation: u64,
+    /// Span used when the windowed mode is turned on.
+    pub window_span: WindowSpan,
+    /// Present in windowed mode; `graph` then only holds its buckets.
+    window: Option<Window>,
+    /// File the frame rules are read from.
+    pub rules_path: Option<PathBuf>,
+    /// Focus or ignore regex being edited, shown in the detail bar.
+    pub prompt: Option<FilterPrompt>,
+    /// Node limit for `graph`.
+    pub budget: NodeBudget,
+    /// Share below which subtrees were last merged into `[other]`.
+    pub pruned: Option<f64>,
+    /// Node count of `graph` and estimated memory of everything the tab
+    /// keeps (graph, baseline, inverted view, window buckets), updated with
+    /// the views.
+    pub footprint: (usize, usize),
 }
 
 impl Default for FlamegraphTab {
@@ -56,6 +184,19 @@ impl Default for FlamegraphTab {
             zoom_path: Vec::new(),
             selection: Selection::default(),
             search: SearchOverlay::default(),
+            inverted: None,
+            baseline: None,
+            diff: None,
+            notice: None,
+            sandwich: None,
+            generation: 0,
+            window_span: WindowSpan::DEFAULT,
+            window: None,
+            rules_path: None,
+            prompt: None,
+            budget: NodeBudget::default(),
+            pruned: None,
+            footprint: (0, 0),
         }
     }
 }
@@ -65,16 +206,466 @@ impl FlamegraphTab {
         if self.frozen {
             return;
         }
-        self.graph.root.merge(new_fg.root);
-        self.graph.root.sort_recursive();
+        let mut cursor = self.cursor_names();
+        match &mut self.window {
+            Some(window) => {
+                let received = self.graph.adopt(&new_fg);
+                self.graph.add_samples(&received);
+                let now = Instant::now();
+                window.push(now, received);
+                if window.expire(now) {
+                    self.rebuild_from_window();
+                }
+            }
+            None => self.graph.merge(&new_fg),
+        }
+        self.enforce_budget(&mut cursor);
+        self.graph.sort();
+        self.refresh_views();
+        self.restore_cursor(&cursor);
         self.profiles_received += 1;
         self.samples_received += samples;
     }
 
+    /// Re-derive the displayed tree after symbols were stored or removed,
+    /// including data collected while the graph is frozen. Nothing is
+    /// rebuilt unless a frame's name changed.
+    pub fn resymbolize(&mut self, resolve: impl FnMut(&NativeFrame) -> Option<Vec<String>>) {
+        let cursor = self.cursor_names();
+        if !self.graph.resymbolize(resolve) {
+            return;
+        }
+        self.graph.sort();
+        self.refresh_views();
+        self.restore_cursor(&cursor);
+    }
+
+    /// Windowed mode: drop data older than the window, also when no new
+    /// profiles arrive.
+    pub fn expire_window(&mut self) {
+        if self.frozen {
+            return;
+        }
+        if let Some(window) = &mut self.window
+            && window.expire(Instant::now())
+        {
+            let mut cursor = self.cursor_names();
+            self.rebuild_from_window();
+            self.enforce_budget(&mut cursor);
+            self.graph.sort();
+            self.refresh_views();
+            self.restore_cursor(&cursor);
+        }
+    }
+
+    pub fn window(&self) -> Option<WindowSpan> {
+        self.window.as_ref().map(|w| w.span)
+    }
+
+    /// Switch between the windowed mode and accumulating the whole
+    /// session. Data shown when the window opens counts as its first bucket.
+    pub fn set_window(&mut self, span: Option<WindowSpan>) {
+        self.window = span.map(|span| {
+            self.window_span = span;
+            let mut buckets = VecDeque::new();
+            if self.graph.root.root().total_value > 0 {
+                buckets.push_back((Instant::now(), self.graph.samples().clone()));
+            }
+            Window { span, buckets }
+        });
+    }
+
+    fn rebuild_from_window(&mut self) {
+        if let Some(window) = &self.window {
+            self.graph
+                .set_samples(window.buckets.iter().map(|(_, samples)| samples));
+        }
+    }
+
+    /// Prune the graph to the node budget and drop the names it no longer
+    /// uses, mapping the names the tab and `cursor` hold.
+    fn enforce_budget(&mut self, cursor: &mut Vec<NameId>) {
+        let pruned = self.graph.enforce_budget(&self.budget);
+        if pruned.is_none() && !self.graph.names_over_budget(&self.budget) {
+            return;
+        }
+        if let Some(share) = pruned {
+            self.pruned = Some(share);
+        }
+        let mut buckets: Vec<&mut FlameTree> = self
+            .window
+            .iter_mut()
+            .flat_map(|window| window.buckets.iter_mut().map(|(_, tree)| tree))
+            .collect();
+        let remap = self.graph.compact(&mut buckets);
+        let map = |names: &mut Vec<NameId>| {
+            let kept: Vec<NameId> = names.iter().map_while(|&n| remap(n)).collect();
+            *names = kept;
+        };
+        map(&mut self.zoom_path);
+        map(cursor);
+        self.search
+            .matches
+            .retain_mut(|(_, id)| remap(*id).map(|to| *id = to).is_some());
+        self.sandwich = self.sandwich.take().and_then(|mut sandwich| {
+            sandwich.name = remap(sandwich.name)?;
+            Some(sandwich)
+        });
+    }
+
+    /// Re-derive the graph (and baseline) with other thread normalization
+    /// or collapsing; applies to data already received.
+    pub fn set_thread_view(&mut self, view: ThreadView) {
+        self.graph.thread_view = view;
+        self.rederive();
+    }
+
+    /// Like [`FlamegraphTab::set_thread_view`], for frame rules.
+    pub fn set_rules(&mut self, rules: FrameRules) {
+        self.graph.rules = rules;
+        self.rederive();
+    }
+
+    fn rederive(&mut self) {
+        self.graph.rederive();
+        self.graph.sort();
+        if let Some(baseline) = &mut self.baseline {
+            baseline.thread_view = self.graph.thread_view.clone();
+            baseline.rules = self.graph.rules.clone();
+            baseline.recursion = self.graph.recursion;
+            baseline.filter = self.graph.filter.clone();
+            baseline.fold_inlines = self.graph.fold_inlines;
+            baseline.rederive();
+            baseline.sort();
+        }
+        self.zoom_path.clear();
+        self.cursor_path.clear();
+        self.scroll_y = 0;
+        self.refresh_views();
+    }
+
+    /// Switch the frame rules off, or re-read them from `rules_path` and
+    /// switch them on, so edits to the file apply without a restart.
+    fn toggle_rules(&mut self) {
+        if self.graph.rules.enabled {
+            let mut rules = self.graph.rules.clone();
+            rules.enabled = false;
+            self.set_rules(rules);
+            self.notice = Some("frame rules off".to_string());
+            return;
+        }
+        let Some(path) = &self.rules_path else {
+            self.notice = Some("no rules file: start with --rules".to_string());
+            return;
+        };
+        match FrameRules::load(path) {
+            Ok(rules) if rules.is_empty() => {
+                self.notice = Some(format!("no rules in {}", path.display()));
+            }
+            Ok(rules) => {
+                self.notice = Some(format!("{} frame rules on", rules.len()));
+                self.set_rules(rules);
+            }
+            Err(e) => self.notice = Some(format!("rules: {e}")),
+        }
+    }
+
+    /// Like [`FlamegraphTab::set_thread_view`], for recursion collapsing.
+    pub fn set_recursion(&mut self, recursion: Recursion) {
+        self.graph.recursion = recursion;
+        self.rederive();
+    }
+
+    fn cycle_recursion(&mut self) {
+        let recursion = self.graph.recursion.next();
+        self.set_recursion(recursion);
+        self.notice = Some(recursion.label().to_string());
+    }
+
+    /// Like [`FlamegraphTab::set_thread_view`], for focus and ignore
+    /// filters.
+    pub fn set_filter(&mut self, filter: StackFilter) {
+        self.graph.filter = filter;
+        self.rederive();
+    }
+
+    fn open_prompt(&mut self, kind: FilterKind) {
+        let current = match kind {
+            FilterKind::Focus => &self.graph.filter.focus,
+            FilterKind::Ignore => &self.graph.filter.ignore,
+        };
+        self.prompt = Some(FilterPrompt {
+            kind,
+            input: current
+                .as_ref()
+                .map(|re| re.to_string())
+                .unwrap_or_default(),
+        });
+    }
+
+    /// Apply the typed regex; an empty one clears the filter.
+    fn apply_prompt(&mut self) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+        let regex = match prompt.input.as_str() {
+            "" => None,
+            input => match Regex::new(input) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    self.notice = Some(format!("{}: {e}", prompt.kind.label()));
+                    return;
+                }
+            },
+        };
+        let mut filter = self.graph.filter.clone();
+        match prompt.kind {
+            FilterKind::Focus => filter.focus = regex,
+            FilterKind::Ignore => filter.ignore = regex,
+        }
+        self.set_filter(filter);
+    }
+
+    fn toggle_reroot(&mut self) {
+        let mut filter = self.graph.filter.clone();
+        filter.reroot = !filter.reroot;
+        self.notice = Some(if filter.reroot {
+            "focused stacks start at the focus".to_string()
+        } else {
+            "focused stacks keep their callers".to_string()
+        });
+        self.set_filter(filter);
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => self.apply_prompt(),
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            KeyCode::Char(c) => prompt.input.push(c),
+            _ => {}
+        }
+    }
+
+    /// Change the sibling order, keeping the cursor on the same frame.
+    pub fn set_order(&mut self, order: FrameOrder) {
+        let cursor = self.cursor_names();
+        self.graph.order = order;
+        self.graph.sort();
+        self.refresh_views();
+        self.restore_cursor(&cursor);
+    }
+
+    fn cycle_order(&mut self) {
+        let order = self.graph.order.next();
+        self.set_order(order);
+        self.notice = Some(order.label().to_string());
+    }
+
+    /// Names from the zoom root down to the cursor, to find the frame
+    /// again once the tree changed.
+    fn cursor_names(&self) -> Vec<NameId> {
+        let tree = self.tree();
+        let zr = get_zoom_node(tree, &self.zoom_path);
+        collect_path_names(tree, zr, &self.cursor_path)
+    }
+
+    /// Point `cursor_path` at the frame `names` leads to, or at the
+    /// deepest part of it still present.
+    fn restore_cursor(&mut self, names: &[NameId]) {
+        let tree = self.tree();
+        let mut node = get_zoom_node(tree, &self.zoom_path);
+        let mut path = Vec::with_capacity(names.len());
+        for &name in names {
+            let Some(idx) = tree.child_position(node, name) else {
+                break;
+            };
+            path.push(idx);
+            node = tree.node(node).children[idx];
+        }
+        self.cursor_path = path;
+    }
+
+    /// Show inlined functions as nodes of their own, or fold them back
+    /// into the function they were inlined into.
+    fn toggle_fold_inlines(&mut self) {
+        self.graph.fold_inlines = !self.graph.fold_inlines;
+        self.rederive();
+        self.notice = Some(if self.graph.fold_inlines {
+            "inlined functions folded into their caller".to_string()
+        } else {
+            "inlined functions shown as frames".to_string()
+        });
+    }
+
+    fn toggle_thread_normalize(&mut self) {
+        let mut view = self.graph.thread_view.clone();
+        view.normalize = !view.normalize;
+        if view.rules.is_empty() {
+            view.rules = ThreadView::default_rules();
+        }
+        self.set_thread_view(view);
+    }
+
+    fn toggle_thread_collapse(&mut self) {
+        let mut view = self.graph.thread_view.clone();
+        view.collapse = !view.collapse;
+        self.set_thread_view(view);
+    }
+
+    /// Tree currently shown: top-down, or bottom-up when inverted.
+    pub fn tree(&self) -> &FlameTree {
+        self.inverted.as_ref().unwrap_or(&self.graph.root)
+    }
+
+    pub fn set_baseline(&mut self, mut baseline: FlameGraph) {
+        baseline.thread_view = self.graph.thread_view.clone();
+        baseline.rules = self.graph.rules.clone();
+        baseline.recursion = self.graph.recursion;
+        baseline.filter = self.graph.filter.clone();
+        baseline.fold_inlines = self.graph.fold_inlines;
+        baseline.rederive();
+        baseline.sort();
+        self.baseline = Some(baseline);
+        self.refresh_views();
+    }
+
+    fn refresh_views(&mut self) {
+        self.generation += 1;
+        if self.inverted.is_some() {
+            self.inverted = Some(self.graph.inverted());
+        }
+        self.footprint = (self.graph.node_count(), self.memory());
+        if self.diff.is_some() {
+            self.diff = self.compute_diff();
+        }
+        if let Some(name) = self.sandwich.as_ref().map(|s| s.name) {
+            self.sandwich = Some(self.graph.root.sandwich(name));
+        }
+    }
+
+    /// Rough heap usage of the graphs and trees the tab keeps, in bytes.
+    fn memory(&self) -> usize {
+        let buckets: usize = self.window.as_ref().map_or(0, |window| {
+            window.buckets.iter().map(|(_, tree)| tree.memory()).sum()
+        });
+        self.graph.memory()
+            + self.baseline.as_ref().map_or(0, FlameGraph::memory)
+            + self.inverted.as_ref().map_or(0, FlameTree::memory)
+            + buckets
+    }
+
+    fn compute_diff(&self) -> Option<Diff> {
+        let base = self.baseline.as_ref()?;
+        let diff = match self.inverted {
+            Some(_) => Diff::new(
+                self.tree(),
+                &self.graph.names,
+                &base.root.inverted(),
+                &base.names,
+            ),
+            None => Diff::new(self.tree(), &self.graph.names, &base.root, &base.names),
+        };
+        Some(diff)
+    }
+
+    fn toggle_inverted(&mut self) {
+        self.inverted = match self.inverted {
+            Some(_) => None,
+            None => Some(self.graph.inverted()),
+        };
+        self.refresh_views();
+        self.zoom_path.clear();
+        self.cursor_path.clear();
+        self.scroll_y = 0;
+    }
+
+    /// Zoom into the occurrence of `name` with the most samples, in the
+    /// top-down view.
+    pub fn show_hottest(&mut self, name: NameId) {
+        let Some(path) = self.graph.root.hottest_path(name) else {
+            return;
+        };
+        if self.inverted.take().is_some() {
+            self.refresh_views();
+        }
+        self.sandwich = None;
+        self.zoom_path = path;
+        self.cursor_path.clear();
+        self.scroll_y = 0;
+    }
+
+    /// Open the sandwich view for the frame under the cursor.
+    fn open_sandwich(&mut self) {
+        let tree = self.tree();
+        let node = get_node(
+            tree,
+            get_zoom_node(tree, &self.zoom_path),
+            &self.cursor_path,
+        );
+        if node != FlameTree::ROOT {
+            self.sandwich = Some(self.graph.root.sandwich(tree.node(node).name));
+        }
+    }
+
+    fn capture_baseline(&mut self) {
+        self.set_baseline(self.graph.clone());
+        self.notice = Some(format!(
+            "baseline captured ({} samples)",
+            self.graph.root.root().total_value
+        ));
+    }
+
+    fn toggle_diff(&mut self) {
+        if self.diff.is_some() {
+            self.diff = None;
+        } else if self.baseline.is_some() {
+            self.diff = self.compute_diff();
+        } else {
+            self.notice = Some("no baseline: press b or start with --baseline".to_string());
+        }
+    }
+
+    /// Write the current graph as folded stacks to the working directory,
+    /// loadable later with `--baseline`.
+    fn export(&mut self) {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("eprofiler-{secs}.folded");
+        let result = File::create(&path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            self.graph.write_folded(&mut out)?;
+            out.flush()
+        });
+        self.notice = Some(match result {
+            Ok(()) => format!("exported to {path}"),
+            Err(e) => format!("export failed: {e}"),
+        });
+    }
+
     pub(crate) fn handle_key(&mut self, key: KeyEvent) {
         if self.search.active {
             return self.handle_search_key(key);
         }
+        if self.prompt.is_some() {
+            return self.handle_prompt_key(key);
+        }
+        self.notice = None;
+        if self.sandwich.is_some() {
+            match key.code {
+                KeyCode::Char('s') | KeyCode::Esc | KeyCode::Backspace => self.sandwich = None,
+                KeyCode::Char('f') | KeyCode::Char(' ') => self.frozen = !self.frozen,
+                _ => {}
+            }
+            return;
+        }
         match key.code {
             KeyCode::Char('f') | KeyCode::Char(' ') => self.frozen = !self.frozen,
             KeyCode::Down | KeyCode::Char('j') => self.move_down(),
@@ -84,6 +675,24 @@ impl FlamegraphTab {
             KeyCode::Enter => self.zoom_in(),
             KeyCode::Esc | KeyCode::Backspace => self.zoom_out(),
             KeyCode::Char('r') => self.reset(),
+            KeyCode::Char('i') => self.toggle_inverted(),
+            KeyCode::Char('b') => self.capture_baseline(),
+            KeyCode::Char('d') => self.toggle_diff(),
+            KeyCode::Char('E') => self.export(),
+            KeyCode::Char('s') => self.open_sandwich(),
+            KeyCode::Char('t') => self.toggle_thread_normalize(),
+            KeyCode::Char('T') => self.toggle_thread_collapse(),
+            KeyCode::Char('R') => self.toggle_rules(),
+            KeyCode::Char('c') => self.cycle_recursion(),
+            KeyCode::Char('F') => self.open_prompt(FilterKind::Focus),
+            KeyCode::Char('I') => self.open_prompt(FilterKind::Ignore),
+            KeyCode::Char('o') => self.toggle_reroot(),
+            KeyCode::Char('O') => self.cycle_order(),
+            KeyCode::Char('n') => self.toggle_fold_inlines(),
+            KeyCode::Char('w') => match self.window {
+                Some(_) => self.set_window(None),
+                None => self.set_window(Some(self.window_span)),
+            },
             KeyCode::Char('/') => {
                 self.search.open();
                 self.refresh_search();
@@ -96,7 +705,7 @@ impl FlamegraphTab {
         match key.code {
             KeyCode::Esc => self.search.close(),
             KeyCode::Enter => {
-                if let Some((name, _)) = self.search.matches.get(self.search.cursor).cloned() {
+                if let Some(&(_, name)) = self.search.matches.get(self.search.cursor) {
                     self.zoom_path = vec![name];
                     self.cursor_path.clear();
                     self.scroll_y = 0;
@@ -109,10 +718,8 @@ impl FlamegraphTab {
                 self.refresh_search();
             }
             KeyCode::Up => self.search.cursor = self.search.cursor.saturating_sub(1),
-            KeyCode::Down => {
-                if self.search.cursor + 1 < self.search.matches.len() {
-                    self.search.cursor += 1;
-                }
+            KeyCode::Down if self.search.cursor + 1 < self.search.matches.len() => {
+                self.search.cursor += 1;
             }
             KeyCode::Char(c) => {
                 self.search.input.push(c);
@@ -125,21 +732,25 @@ impl FlamegraphTab {
 
     fn refresh_search(&mut self) {
         let query = self.search.input.to_lowercase();
-        self.search.matches = self
-            .graph
-            .root
+        let tree = self.tree();
+        self.search.matches = tree
+            .root()
             .children
             .iter()
-            .enumerate()
-            .filter(|(_, c)| query.is_empty() || c.name.to_lowercase().contains(&query))
-            .map(|(i, c)| (c.name.clone(), i))
+            .map(|&c| (self.graph.name(tree.node(c).name), tree.node(c).name))
+            .filter(|(name, _)| query.is_empty() || name.to_lowercase().contains(&query))
+            .map(|(name, id)| (name.to_string(), id))
             .collect();
     }
 
     fn move_down(&mut self) {
         let has_children = {
-            let zr = get_zoom_node(&self.graph.root, &self.zoom_path);
-            !get_node(zr, &self.cursor_path).children.is_empty()
+            let tree = self.tree();
+            let zr = get_zoom_node(tree, &self.zoom_path);
+            !tree
+                .node(get_node(tree, zr, &self.cursor_path))
+                .children
+                .is_empty()
         };
         if has_children {
             self.cursor_path.push(0);
@@ -161,10 +772,15 @@ impl FlamegraphTab {
             if self.cursor_path.is_empty() {
                 return;
             }
-            let zr = get_zoom_node(&self.graph.root, &self.zoom_path);
-            get_node(zr, &self.cursor_path[..self.cursor_path.len() - 1])
-                .children
-                .len()
+            let tree = self.tree();
+            let zr = get_zoom_node(tree, &self.zoom_path);
+            tree.node(get_node(
+                tree,
+                zr,
+                &self.cursor_path[..self.cursor_path.len() - 1],
+            ))
+            .children
+            .len()
         };
         if let Some(last) = self.cursor_path.last_mut()
             && *last + 1 < sibling_count
@@ -178,8 +794,8 @@ impl FlamegraphTab {
             return;
         }
         let names = {
-            let zr = get_zoom_node(&self.graph.root, &self.zoom_path);
-            collect_path_names(zr, &self.cursor_path)
+            let zr = get_zoom_node(self.tree(), &self.zoom_path);
+            collect_path_names(self.tree(), zr, &self.cursor_path)
         };
         self.zoom_path.extend(names);
         self.cursor_path.clear();
@@ -194,7 +810,24 @@ impl FlamegraphTab {
     }
 
     fn reset(&mut self) {
+        let thread_view = std::mem::take(&mut self.graph.thread_view);
+        let rules = std::mem::take(&mut self.graph.rules);
+        let recursion = self.graph.recursion;
+        let filter = std::mem::take(&mut self.graph.filter);
+        let order = self.graph.order;
+        let fold_inlines = self.graph.fold_inlines;
         self.graph = FlameGraph::new();
+        self.graph.thread_view = thread_view;
+        self.graph.rules = rules;
+        self.graph.recursion = recursion;
+        self.graph.filter = filter;
+        self.graph.order = order;
+        self.graph.fold_inlines = fold_inlines;
+        if let Some(window) = &mut self.window {
+            window.buckets.clear();
+        }
+        self.pruned = None;
+        self.refresh_views();
         self.profiles_received = 0;
         self.samples_received = 0;
         self.zoom_path.clear();
@@ -203,13 +836,147 @@ impl FlamegraphTab {
     }
 }
 
-fn collect_path_names(root: &FlameNode, index_path: &[usize]) -> Vec<String> {
+fn collect_path_names(tree: &FlameTree, from: NodeId, index_path: &[usize]) -> Vec<NameId> {
     index_path
         .iter()
-        .scan(root, |node, &idx| {
-            let child = node.children.get(idx)?;
-            *node = child;
-            Some(child.name.clone())
+        .scan(from, |node, &idx| {
+            *node = *tree.node(*node).children.get(idx)?;
+            Some(tree.node(*node).name)
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flamegraph::Frame;
+
+    #[test]
+    fn export_window_keeps_only_recent_profiles() {
+        let mut tab = FlamegraphTab::default();
+        tab.set_window(Some(WindowSpan::Exports(2)));
+        for (stack, value) in [("t;old", 5), ("t;mid", 3), ("t;new", 2)] {
+            let graph = FlameGraph::from_folded(&format!("{stack} {value}")).unwrap();
+            tab.merge(graph, value as u64);
+        }
+
+        let root = &tab.graph.root;
+        assert_eq!(root.root().total_value, 5);
+        let thread = root.root().children[0];
+        let names: Vec<&str> = root
+            .node(thread)
+            .children
+            .iter()
+            .map(|&c| tab.graph.name(root.node(c).name))
+            .collect();
+        assert_eq!(names, ["mid", "new"]);
+        assert_eq!(tab.samples_received, 10);
+    }
+
+    #[test]
+    fn expired_window_is_rebuilt_within_budget() {
+        let mut tab = FlamegraphTab::default();
+        tab.set_window(Some(WindowSpan::DEFAULT));
+        let graph = FlameGraph::from_folded("t;hot;a 90\nt;cold1 3\nt;cold2;x 2\n").unwrap();
+        tab.merge(graph, 95);
+
+        let stale = tab.graph.samples().clone();
+        let long_ago = Instant::now()
+            .checked_sub(Duration::from_secs(120))
+            .unwrap();
+        let window = tab.window.as_mut().unwrap();
+        window.buckets.push_front((long_ago, stale));
+        tab.budget = NodeBudget {
+            max_nodes: 6,
+            min_share: 0.05,
+        };
+        tab.expire_window();
+
+        assert_eq!(tab.pruned, Some(0.05));
+        assert_eq!(tab.graph.node_count(), 5);
+        assert_eq!(tab.graph.root.root().total_value, 95);
+    }
+
+    #[test]
+    fn pruned_native_frames_release_their_names() {
+        let mut tab = FlamegraphTab {
+            budget: NodeBudget {
+                max_nodes: 50,
+                min_share: 0.01,
+            },
+            ..Default::default()
+        };
+        for address in 0..2000u64 {
+            let mut graph = FlameGraph::new();
+            let frame = Frame {
+                name: format!("jit+0x{address:x}"),
+                inlined: Vec::new(),
+                native: Some(NativeFrame {
+                    mapping: "jit".to_string(),
+                    address,
+                    file_offset: None,
+                    pid: None,
+                }),
+            };
+            let stack = [graph.names.intern("t"), graph.intern_frame(&frame)];
+            graph.add_stack(&stack, 1);
+            // Every other address resolves to one of a few functions.
+            graph.symbolize(|native| {
+                (native.address % 2 == 0).then(|| vec![format!("fn{}", native.address % 10)])
+            });
+            tab.merge(graph, 1);
+        }
+
+        assert!(tab.graph.names.len() <= 4 * 50, "{}", tab.graph.names.len());
+        let root = &tab.graph.root;
+        let thread = root.node(root.root().children[0]);
+        let names: Vec<&str> = thread
+            .children
+            .iter()
+            .map(|&c| tab.graph.name(root.node(c).name))
+            .collect();
+        for name in ["[other]", "fn0", "fn2", "fn4", "fn6", "fn8"] {
+            assert!(names.contains(&name), "{name} missing from {names:?}");
+        }
+        assert_eq!(root.root().total_value, 2000);
+    }
+
+    #[test]
+    fn search_zooms_to_the_picked_thread_after_updates() {
+        let mut tab = FlamegraphTab::default();
+        let graph = FlameGraph::from_folded("a;main 2\nb;main 1\n").unwrap();
+        tab.merge(graph, 3);
+        tab.handle_key(KeyEvent::from(KeyCode::Char('/')));
+        tab.handle_key(KeyEvent::from(KeyCode::Char('b')));
+
+        // `b` becomes the hottest thread while the search is open.
+        let more = FlameGraph::from_folded("b;main 5\n").unwrap();
+        tab.merge(more, 5);
+        tab.handle_key(KeyEvent::from(KeyCode::Enter));
+
+        assert_eq!(tab.zoom_path, [tab.graph.names.get("b").unwrap()]);
+    }
+
+    #[test]
+    fn cursor_stays_on_frame_when_order_changes() {
+        let mut tab = FlamegraphTab::default();
+        let graph = FlameGraph::from_folded("t;zeta 1\nt;alpha 2\nt;mid;leaf 3\n").unwrap();
+        tab.merge(graph, 6);
+        // t → mid → leaf, mid being the hottest child.
+        tab.cursor_path = vec![0, 0, 0];
+        let leaf = tab.cursor_names();
+
+        tab.set_order(FrameOrder::Name);
+        assert_eq!(tab.cursor_path, [0, 1, 0]);
+        assert_eq!(tab.cursor_names(), leaf);
+
+        tab.set_order(FrameOrder::Insertion);
+        assert_eq!(tab.cursor_path, [0, 2, 0]);
+
+        let more = FlameGraph::from_folded("t;alpha 10\n").unwrap();
+        tab.set_order(FrameOrder::Value);
+        tab.merge(more, 10);
+        assert_eq!(tab.cursor_path, [0, 1, 0]);
+        assert_eq!(tab.cursor_names(), leaf);
+    }
+}
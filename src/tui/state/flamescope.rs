@@ -143,10 +143,8 @@ impl FlamescopeTab {
                 self.auto_scroll = false;
                 self.cursor_col = self.cursor_col.saturating_sub(1);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if self.cursor_row + 1 < SUBSECOND_ROWS {
-                    self.cursor_row += 1;
-                }
+            KeyCode::Down | KeyCode::Char('j') if self.cursor_row + 1 < SUBSECOND_ROWS => {
+                self.cursor_row += 1;
             }
             KeyCode::Up | KeyCode::Char('k') => {
                 self.cursor_row = self.cursor_row.saturating_sub(1);
@@ -185,10 +183,8 @@ impl FlamescopeTab {
                 self.refresh_search();
             }
             KeyCode::Up => self.search.cursor = self.search.cursor.saturating_sub(1),
-            KeyCode::Down => {
-                if self.search.cursor + 1 < self.search.matches.len() {
-                    self.search.cursor += 1;
-                }
+            KeyCode::Down if self.search.cursor + 1 < self.search.matches.len() => {
+                self.search.cursor += 1;
             }
             KeyCode::Char(c) => {
                 self.search.input.push(c);
@@ -0,0 +1,270 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+use crate::flamegraph::{FlameGraph, NameId};
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortColumn {
+    #[default]
+    SelfValue,
+    Total,
+    CallPaths,
+    Name,
+}
+
+impl SortColumn {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::SelfValue => "self",
+            Self::Total => "total",
+            Self::CallPaths => "paths",
+            Self::Name => "name",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::SelfValue => Self::Total,
+            Self::Total => Self::CallPaths,
+            Self::CallPaths => Self::Name,
+            Self::Name => Self::SelfValue,
+        }
+    }
+}
+
+pub struct FunctionRow {
+    pub name: NameId,
+    pub label: String,
+    pub self_value: i64,
+    pub total_value: i64,
+    pub call_paths: usize,
+}
+
+#[derive(Default)]
+pub struct FunctionFilter {
+    pub active: bool,
+    pub input: String,
+}
+
+/// `perf report`-style table of every function in the flamegraph.
+#[derive(Default)]
+pub struct FunctionsTab {
+    rows: Vec<FunctionRow>,
+    /// Indices into `rows` matching the filter, in display order.
+    pub visible: Vec<usize>,
+    /// Samples in the whole graph, for percentages.
+    pub total: i64,
+    pub sort: SortColumn,
+    pub reversed: bool,
+    pub filter: FunctionFilter,
+    pub cursor: usize,
+    pub scroll: usize,
+    /// [`super::FlamegraphTab::generation`] the rows were built from.
+    generation: Option<u64>,
+}
+
+impl FunctionsTab {
+    pub fn row(&self, idx: usize) -> &FunctionRow {
+        &self.rows[self.visible[idx]]
+    }
+
+    /// Rebuild the rows if the graph changed since the last call.
+    pub fn sync(&mut self, graph: &FlameGraph, generation: u64) {
+        if self.generation == Some(generation) {
+            return;
+        }
+        self.generation = Some(generation);
+        self.total = graph.root.root().total_value;
+        self.rows = graph
+            .root
+            .functions(!graph.thread_view.collapse)
+            .into_iter()
+            .map(|f| FunctionRow {
+                name: f.name,
+                label: graph.name(f.name).to_string(),
+                self_value: f.self_value,
+                total_value: f.total_value,
+                call_paths: f.call_paths,
+            })
+            .collect();
+        self.sort_rows();
+    }
+
+    fn sort_rows(&mut self) {
+        // Rows come from a hash map, so equal keys fall back to the name to
+        // keep their order stable across refreshes.
+        let by_name =
+            |a: &FunctionRow, b: &FunctionRow| a.label.cmp(&b.label).then(a.name.cmp(&b.name));
+        let rows = &mut self.rows;
+        match self.sort {
+            SortColumn::SelfValue => rows.sort_by(|a, b| {
+                (b.self_value, b.total_value)
+                    .cmp(&(a.self_value, a.total_value))
+                    .then_with(|| by_name(a, b))
+            }),
+            SortColumn::Total => rows.sort_by(|a, b| {
+                (b.total_value, b.self_value)
+                    .cmp(&(a.total_value, a.self_value))
+                    .then_with(|| by_name(a, b))
+            }),
+            SortColumn::CallPaths => rows.sort_by(|a, b| {
+                (b.call_paths, b.total_value)
+                    .cmp(&(a.call_paths, a.total_value))
+                    .then_with(|| by_name(a, b))
+            }),
+            SortColumn::Name => rows.sort_by(by_name),
+        }
+        if self.reversed {
+            self.rows.reverse();
+        }
+        self.refresh_filter();
+    }
+
+    fn refresh_filter(&mut self) {
+        let query = self.filter.input.to_lowercase();
+        self.visible = self
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| query.is_empty() || r.label.to_lowercase().contains(&query))
+            .map(|(i, _)| i)
+            .collect();
+        self.cursor = self.cursor.min(self.visible.len().saturating_sub(1));
+    }
+
+    /// Returns the function to show in the flamegraph when one is picked.
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> Option<NameId> {
+        if self.filter.active {
+            self.handle_filter_key(key);
+            return None;
+        }
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if self.cursor + 1 < self.visible.len() => {
+                self.cursor += 1
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.reversed = false;
+                self.sort_rows();
+            }
+            KeyCode::Char('S') => {
+                self.reversed = !self.reversed;
+                self.sort_rows();
+            }
+            KeyCode::Char('/') => self.filter.active = true,
+            KeyCode::Esc => {
+                self.filter.input.clear();
+                self.refresh_filter();
+            }
+            KeyCode::Enter => return self.visible.get(self.cursor).map(|&i| self.rows[i].name),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.filter.active = false;
+                self.filter.input.clear();
+            }
+            KeyCode::Enter => self.filter.active = false,
+            KeyCode::Backspace => {
+                self.filter.input.pop();
+            }
+            KeyCode::Char(c) => self.filter.input.push(c),
+            _ => return,
+        }
+        self.cursor = 0;
+        self.refresh_filter();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::state::FlamegraphTab;
+
+    fn tab_for(folded: &str) -> (FlamegraphTab, FunctionsTab) {
+        let mut fg = FlamegraphTab::default();
+        let graph = FlameGraph::from_folded(folded).unwrap();
+        let samples = graph.root.root().total_value as u64;
+        fg.merge(graph, samples);
+        let mut top = FunctionsTab::default();
+        top.sync(&fg.graph, fg.generation);
+        (fg, top)
+    }
+
+    fn labels(top: &FunctionsTab) -> Vec<&str> {
+        (0..top.visible.len())
+            .map(|i| top.row(i).label.as_str())
+            .collect()
+    }
+
+    fn press(top: &mut FunctionsTab, keys: &str) {
+        for c in keys.chars() {
+            top.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn equal_keys_are_ordered_by_name() {
+        let (_, mut top) = tab_for("t;main;b 3\nt;main;a 3\nt;main;c 1\nt;d 5\n");
+        assert_eq!(labels(&top), ["d", "a", "b", "c", "main"]);
+
+        press(&mut top, "s");
+        assert!(top.sort == SortColumn::Total);
+        assert_eq!(labels(&top), ["main", "d", "a", "b", "c"]);
+
+        press(&mut top, "ssS");
+        assert!(top.sort == SortColumn::Name);
+        assert_eq!(labels(&top), ["main", "d", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn filter_matches_names_case_insensitively() {
+        let (_, mut top) = tab_for("t;Main;parse 3\nt;main;lex 1\nt;print 2\n");
+        press(&mut top, "/PAR");
+        assert_eq!(labels(&top), ["parse"]);
+
+        top.handle_key(KeyEvent::from(KeyCode::Backspace));
+        top.handle_key(KeyEvent::from(KeyCode::Backspace));
+        assert_eq!(labels(&top), ["parse", "print"]);
+
+        // Leaving the filter keeps it applied until cleared with Esc.
+        top.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert!(!top.filter.active);
+        assert_eq!(top.visible.len(), 2);
+        top.handle_key(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(top.visible.len(), 5);
+    }
+
+    #[test]
+    fn cursor_is_clamped_when_rows_shrink() {
+        let (fg, mut top) = tab_for("t;a 4\nt;b 3\nt;c 2\nt;d 1\n");
+        press(&mut top, "jjjjj");
+        assert_eq!(top.cursor, 3);
+
+        let smaller = FlameGraph::from_folded("t;a 1\nt;b 1\n").unwrap();
+        top.sync(&smaller, fg.generation + 1);
+        assert_eq!(top.cursor, 1);
+
+        press(&mut top, "/zzz");
+        assert!(top.visible.is_empty());
+        assert_eq!(top.cursor, 0);
+        assert_eq!(top.handle_key(KeyEvent::from(KeyCode::Enter)), None);
+    }
+
+    #[test]
+    fn enter_shows_hottest_path_in_flamegraph() {
+        let (mut fg, mut top) = tab_for("t;main;parse 2\nt;run;parse 5\nt;main;lex 6\n");
+        assert_eq!(labels(&top), ["parse", "lex", "main", "run"]);
+
+        let name = top.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(fg.graph.name(name), "parse");
+        fg.show_hottest(name);
+
+        let path: Vec<&str> = fg.zoom_path.iter().map(|&n| fg.graph.name(n)).collect();
+        assert_eq!(path, ["t", "run", "parse"]);
+    }
+}
@@ -1,27 +1,35 @@
 mod executables;
 mod flamegraph;
 mod flamescope;
+mod functions;
 
-pub use executables::ExecutablesTab;
-pub use flamegraph::FlamegraphTab;
+pub use executables::{ExecutablesTab, JobsPanel};
+pub use flamegraph::{FlamegraphTab, WindowSpan};
 pub use flamescope::FlamescopeTab;
+pub use functions::{FunctionsTab, SortColumn};
 
 use std::path::PathBuf;
 
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+use crate::jobs::JobId;
 use crate::storage::{ExecutableInfo, FileId};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ActiveTab {
     Flamegraph,
     Flamescope,
+    Functions,
     Executables,
 }
 
 pub enum Action {
     LoadSymbols(PathBuf, Option<String>),
+    LoadProcess(u32),
+    CancelJob(JobId),
     RemoveSymbols(String, FileId),
+    CompactStore,
+    RefreshStore,
     None,
 }
 
@@ -31,6 +39,7 @@ pub struct State {
     pub active_tab: ActiveTab,
     pub fg: FlamegraphTab,
     pub fs: FlamescopeTab,
+    pub top: FunctionsTab,
     pub exe: ExecutablesTab,
 }
 
@@ -42,6 +51,7 @@ impl State {
             active_tab: ActiveTab::Flamegraph,
             fg: FlamegraphTab::default(),
             fs: FlamescopeTab::default(),
+            top: FunctionsTab::default(),
             exe: ExecutablesTab::from(initial_exes),
         }
     }
@@ -52,15 +62,25 @@ impl State {
             return Action::None;
         }
 
-        let overlay_active =
-            self.fg.search.active || self.fs.search.active || self.exe.path_input.active;
+        let overlay_active = self.fg.search.active
+            || self.fg.prompt.is_some()
+            || self.fs.search.active
+            || self.top.filter.active
+            || self.exe.path_input.active
+            || self.exe.pid_input.active
+            || self.exe.detail;
 
         if key.code == KeyCode::Tab && !overlay_active {
             self.active_tab = match self.active_tab {
                 ActiveTab::Flamegraph => ActiveTab::Flamescope,
-                ActiveTab::Flamescope => ActiveTab::Executables,
+                ActiveTab::Flamescope => ActiveTab::Functions,
+                ActiveTab::Functions => ActiveTab::Executables,
                 ActiveTab::Executables => ActiveTab::Flamegraph,
             };
+            // Last-lookup times change while profiles stream in.
+            if self.active_tab == ActiveTab::Executables {
+                return Action::RefreshStore;
+            }
             return Action::None;
         }
 
@@ -78,6 +98,13 @@ impl State {
                 self.fs.handle_key(key);
                 Action::None
             }
+            ActiveTab::Functions => {
+                if let Some(name) = self.top.handle_key(key) {
+                    self.fg.show_hottest(name);
+                    self.active_tab = ActiveTab::Flamegraph;
+                }
+                Action::None
+            }
             ActiveTab::Executables => self.exe.handle_key(key),
         }
     }
@@ -8,8 +8,15 @@ use ratatui::{
 };
 
 use super::flamescope_layout::FlamescopeLayout;
-use super::state::{ActiveTab, ExecutablesTab, FlamegraphTab, FlamescopeTab, State};
-use crate::flamegraph::{cursor_frame_rect, get_zoom_node, layout_frames, thread_rank};
+use super::state::{
+    ActiveTab, ExecutablesTab, FlamegraphTab, FlamescopeTab, FunctionsTab, JobsPanel, SortColumn,
+    State,
+};
+use crate::flamegraph::{
+    FlameTree, FrameRect, Sandwich, cursor_frame_rect, get_zoom_node, layout_frames, share,
+    share_change, thread_rank,
+};
+use crate::jobs::{JobState, Phase};
 
 const BG: Color = Color::Rgb(16, 16, 22);
 const ACCENT: Color = Color::Rgb(59, 130, 246);
@@ -20,7 +27,7 @@ const SEP_COLOR: Color = Color::Rgb(35, 35, 45);
 pub fn render(state: &mut State, frame: &mut Frame) {
     let area = frame.area();
 
-    if state.fg.graph.root.total_value == 0 && state.active_tab == ActiveTab::Flamegraph {
+    if state.fg.graph.root.root().total_value == 0 && state.active_tab == ActiveTab::Flamegraph {
         render_waiting(frame, area, &state.listen_addr);
         return;
     }
@@ -41,14 +48,20 @@ pub fn render(state: &mut State, frame: &mut Frame) {
     match state.active_tab {
         ActiveTab::Flamegraph => {
             render_detail_bar(&state.fg, frame, chunks[1]);
-            render_flamegraph(&mut state.fg, frame, chunks[2]);
-            render_keyhints(
-                state.fg.search.active,
-                FLAMEGRAPH_KEYS,
-                SEARCH_KEYS,
-                frame,
-                chunks[3],
-            );
+            if state.fg.sandwich.is_some() {
+                render_sandwich(&state.fg, frame, chunks[2]);
+                render_keyhints(false, SANDWICH_KEYS, SEARCH_KEYS, frame, chunks[3]);
+            } else {
+                render_flamegraph(&mut state.fg, frame, chunks[2]);
+                let prompt = state.fg.prompt.is_some();
+                render_keyhints(
+                    state.fg.search.active || prompt,
+                    FLAMEGRAPH_KEYS,
+                    if prompt { FILTER_KEYS } else { SEARCH_KEYS },
+                    frame,
+                    chunks[3],
+                );
+            }
 
             if state.fg.search.active {
                 let items: Vec<&str> = state
@@ -58,20 +71,25 @@ pub fn render(state: &mut State, frame: &mut Frame) {
                     .iter()
                     .map(|(name, _)| name.as_str())
                     .collect();
+                let inverted = state.fg.inverted.is_some();
                 render_overlay(
                     frame,
                     chunks[2],
                     &OverlayProps {
-                        title: " thread.name ",
+                        title: if inverted {
+                            " leaf function "
+                        } else {
+                            " thread.name "
+                        },
                         input: &state.fg.search.input,
                         items: &items,
                         cursor: state.fg.search.cursor,
                         border_color: Color::Rgb(245, 166, 35),
                         max_visible: 3,
-                        empty_hint: if state.fg.search.input.is_empty() {
-                            "type to filter threads..."
-                        } else {
-                            "no matches"
+                        empty_hint: match (state.fg.search.input.is_empty(), inverted) {
+                            (true, false) => "type to filter threads...",
+                            (true, true) => "type to filter leaf functions...",
+                            (false, _) => "no matches",
                         },
                         popup_width: 50,
                     },
@@ -111,13 +129,48 @@ pub fn render(state: &mut State, frame: &mut Frame) {
                 );
             }
         }
+        ActiveTab::Functions => {
+            state.top.sync(&state.fg.graph, state.fg.generation);
+            render_functions_bar(&state.top, frame, chunks[1]);
+            render_functions_table(&mut state.top, frame, chunks[2]);
+            render_keyhints(
+                state.top.filter.active,
+                FUNCTIONS_KEYS,
+                FILTER_KEYS,
+                frame,
+                chunks[3],
+            );
+        }
         ActiveTab::Executables => {
-            render_exe_status_bar(state.exe.status.as_deref(), frame, chunks[1]);
-            render_exe_table(&mut state.exe, frame, chunks[2]);
+            render_exe_status_bar(&state.exe, frame, chunks[1]);
+            if state.exe.jobs.active {
+                let panel_height = (state.exe.jobs.list.len().clamp(1, 8) + 1) as u16;
+                let parts = Layout::new(
+                    Direction::Vertical,
+                    [Constraint::Min(0), Constraint::Length(panel_height)],
+                )
+                .split(chunks[2]);
+                render_exe_table(&mut state.exe, frame, parts[0]);
+                render_jobs_panel(&mut state.exe.jobs, frame, parts[1]);
+            } else {
+                render_exe_table(&mut state.exe, frame, chunks[2]);
+            }
+            let overlay_keys = if state.exe.detail {
+                DETAIL_KEYS
+            } else if state.exe.pid_input.active {
+                PID_INPUT_KEYS
+            } else if state.exe.path_input.active {
+                EXE_INPUT_KEYS
+            } else {
+                JOBS_KEYS
+            };
             render_keyhints(
-                state.exe.path_input.active,
+                state.exe.path_input.active
+                    || state.exe.pid_input.active
+                    || state.exe.jobs.active
+                    || state.exe.detail,
                 EXE_KEYS,
-                EXE_INPUT_KEYS,
+                overlay_keys,
                 frame,
                 chunks[3],
             );
@@ -144,6 +197,27 @@ pub fn render(state: &mut State, frame: &mut Frame) {
                     },
                 );
             }
+
+            if state.exe.pid_input.active {
+                render_overlay(
+                    frame,
+                    chunks[2],
+                    &OverlayProps {
+                        title: " process ID ",
+                        input: &state.exe.pid_input.input,
+                        items: &[],
+                        cursor: 0,
+                        border_color: ACCENT,
+                        max_visible: 1,
+                        empty_hint: "load symbols for every executable mapping",
+                        popup_width: 50,
+                    },
+                );
+            }
+
+            if state.exe.detail {
+                render_exe_detail(&state.exe, frame, chunks[2]);
+            }
         }
     }
 }
@@ -257,7 +331,7 @@ fn center_str(buf: &mut Buffer, area: Rect, y: u16, text: &str, style: Style) {
 fn render_header(state: &State, frame: &mut Frame, area: Rect) {
     let sep = " │ ".fg(Color::Rgb(55, 55, 65));
 
-    let left_spans: Vec<Span> = vec![
+    let mut left_spans: Vec<Span> = vec![
         Span::styled(" ◆ ", Style::default().fg(ACCENT)),
         Span::styled(
             "eprofiler-tui",
@@ -274,6 +348,37 @@ fn render_header(state: &State, frame: &mut Frame, area: Rect) {
         format!("{} samples", format_count(state.fg.samples_received))
             .fg(Color::Rgb(110, 110, 130)),
     ];
+    let (nodes, memory) = state.fg.footprint;
+    left_spans.push(" │ ".fg(Color::Rgb(55, 55, 65)));
+    left_spans.push(
+        format!(
+            "{} nodes · {}",
+            format_count(nodes as u64),
+            format_bytes(memory as u64)
+        )
+        .fg(Color::Rgb(110, 110, 130)),
+    );
+    if let Some(share) = state.fg.pruned {
+        left_spans
+            .push(format!(" (<{:.2}% in [other])", share * 100.0).fg(Color::Rgb(234, 179, 8)));
+    }
+    let filter = &state.fg.graph.filter;
+    if filter.is_active() {
+        left_spans.push(" │ ".fg(Color::Rgb(55, 55, 65)));
+        let mut parts = Vec::new();
+        if let Some(focus) = &filter.focus {
+            let arrow = if filter.reroot { "⤒" } else { "" };
+            parts.push(format!("focus{arrow} /{focus}/"));
+        }
+        if let Some(ignore) = &filter.ignore {
+            parts.push(format!("ignore /{ignore}/"));
+        }
+        left_spans.push(parts.join(" · ").fg(Color::Rgb(245, 166, 35)));
+    }
+    if let Some(span) = state.fg.window() {
+        left_spans.push(" │ ".fg(Color::Rgb(55, 55, 65)));
+        left_spans.push(format!("◷ {}", span.label()).fg(Color::Rgb(20, 184, 166)));
+    }
     frame.render_widget(Paragraph::new(Line::from(left_spans)), area);
 
     let buf = frame.buffer_mut();
@@ -303,6 +408,7 @@ fn render_header(state: &State, frame: &mut Frame, area: Rect) {
     let tabs: &[(&str, ActiveTab)] = &[
         ("Flamegraph", ActiveTab::Flamegraph),
         ("Flamescope", ActiveTab::Flamescope),
+        ("Functions", ActiveTab::Functions),
         ("Executables", ActiveTab::Executables),
     ];
     let tabs_width: usize =
@@ -327,21 +433,59 @@ fn render_header(state: &State, frame: &mut Frame, area: Rect) {
 }
 
 fn render_detail_bar(fg: &FlamegraphTab, frame: &mut Frame, area: Rect) {
+    if let Some(sandwich) = &fg.sandwich {
+        return render_sandwich_bar(fg, sandwich, frame, area);
+    }
+    if let Some(prompt) = &fg.prompt {
+        let spans = vec![
+            format!(" {} regex: ", prompt.kind.label()).fg(DIM),
+            prompt.input.clone().fg(BRIGHT),
+            "▏".fg(ACCENT),
+            "  (empty to clear)".fg(DIM),
+        ];
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+        return;
+    }
     let sel = &fg.selection;
-    if sel.name.is_empty() && fg.zoom_path.is_empty() {
+    if sel.name.is_empty()
+        && fg.zoom_path.is_empty()
+        && fg.inverted.is_none()
+        && fg.diff.is_none()
+        && fg.notice.is_none()
+    {
         return;
     }
 
     let sep = " │ ".fg(Color::Rgb(55, 55, 65));
-    let root_total = get_zoom_node(&fg.graph.root, &fg.zoom_path).total_value;
+    let tree = fg.tree();
+    let root_total = tree.node(get_zoom_node(tree, &fg.zoom_path)).total_value;
 
     let mut spans: Vec<Span> = Vec::new();
 
+    if let Some(notice) = &fg.notice {
+        spans.push(format!(" {notice} ").fg(Color::Rgb(34, 197, 94)));
+        spans.push(sep.clone());
+    }
+
+    if fg.diff.is_some() {
+        spans.push(" ± diff ".fg(Color::Rgb(239, 68, 68)).bold());
+        if fg.inverted.is_some() || !fg.zoom_path.is_empty() || !sel.name.is_empty() {
+            spans.push(sep.clone());
+        }
+    }
+
+    if fg.inverted.is_some() {
+        spans.push(" ⇅ inverted ".fg(Color::Rgb(168, 85, 247)).bold());
+        if !fg.zoom_path.is_empty() || !sel.name.is_empty() {
+            spans.push(sep.clone());
+        }
+    }
+
     if !fg.zoom_path.is_empty() {
         spans.push(
             format!(
                 " zoomed: {} ",
-                fg.zoom_path.last().unwrap_or(&String::new())
+                fg.zoom_path.last().map_or("", |&name| fg.graph.name(name))
             )
             .fg(ACCENT)
             .bold(),
@@ -365,6 +509,9 @@ fn render_detail_bar(fg: &FlamegraphTab, frame: &mut Frame, area: Rect) {
             truncate(&sel.name, 40),
             Style::default().fg(BRIGHT).add_modifier(Modifier::BOLD),
         ));
+        if sel.inlined {
+            spans.push(" inlined".fg(DIM).italic());
+        }
         spans.push(sep.clone());
         spans.push("self: ".fg(DIM));
         spans.push(
@@ -385,14 +532,103 @@ fn render_detail_bar(fg: &FlamegraphTab, frame: &mut Frame, area: Rect) {
             )
             .fg(Color::Rgb(234, 179, 8)),
         );
-        spans.push(sep);
+        spans.push(sep.clone());
         spans.push("depth: ".fg(DIM));
         spans.push(sel.depth.to_string().fg(Color::Rgb(130, 130, 150)));
+
+        if let Some(diff) = &fg.diff {
+            let (base_self, base_total) = sel.baseline.unwrap_or((0, 0));
+            let before = share(base_total, diff.base_total) * 100.0;
+            let after = share(sel.total_value, diff.total) * 100.0;
+            spans.push(sep);
+            spans.push("before: ".fg(DIM));
+            spans.push(
+                format!(
+                    "{} / {} ",
+                    format_count(base_self as u64),
+                    format_count(base_total as u64)
+                )
+                .fg(BRIGHT),
+            );
+            spans.push(
+                format!("{before:.1}% → {after:.1}%").fg(diff_color(share_change(before, after))),
+            );
+        }
     }
 
     frame.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
+fn render_sandwich_bar(fg: &FlamegraphTab, sandwich: &Sandwich, frame: &mut Frame, area: Rect) {
+    let sep = " │ ".fg(Color::Rgb(55, 55, 65));
+    let all = fg.graph.root.root().total_value;
+    let spans = vec![
+        " ⧗ sandwich ".fg(Color::Rgb(20, 184, 166)).bold(),
+        sep.clone(),
+        Span::styled(
+            truncate(fg.graph.name(sandwich.name), 40),
+            Style::default().fg(BRIGHT).add_modifier(Modifier::BOLD),
+        ),
+        sep.clone(),
+        "self: ".fg(DIM),
+        format!(
+            "{} ({:.1}%)",
+            format_count(sandwich.self_value as u64),
+            share(sandwich.self_value, all) * 100.0
+        )
+        .fg(Color::Rgb(249, 115, 22)),
+        sep,
+        "total: ".fg(DIM),
+        format!(
+            "{} ({:.1}%)",
+            format_count(sandwich.total_value as u64),
+            share(sandwich.total_value, all) * 100.0
+        )
+        .fg(Color::Rgb(234, 179, 8)),
+    ];
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Callers drawn upwards from the middle of the area, callees downwards,
+/// both starting at the selected function.
+fn render_sandwich(fg: &FlamegraphTab, frame: &mut Frame, area: Rect) {
+    let Some(sandwich) = &fg.sandwich else {
+        return;
+    };
+    let buf = frame.buffer_mut();
+    if area.width < 4 || area.height < 2 {
+        return;
+    }
+    if sandwich.total_value <= 0 {
+        render_empty_fg(buf, area);
+        return;
+    }
+
+    let all = fg.graph.root.root().total_value;
+    let callers_height = area.height / 2;
+    let halves = [
+        (&sandwich.callers, callers_height, true),
+        (&sandwich.callees, area.height - callers_height, false),
+    ];
+    for (tree, height, upwards) in halves {
+        for fr in layout_frames(tree, FlameTree::ROOT, area.width, Some(0)) {
+            if fr.depth >= height as usize {
+                continue;
+            }
+            let y = if upwards {
+                area.y + callers_height - 1 - fr.depth as u16
+            } else {
+                area.y + callers_height + fr.depth as u16
+            };
+            let heat = share(fr.self_value, fr.total_value);
+            let name = fg.graph.name(fr.name);
+            let bg = flame_color(name, heat, fr.palette_index);
+            let row = Rect::new(area.x, y, area.width, 1);
+            draw_frame(buf, row, &fr, name, bg, fr.depth == 0, all);
+        }
+    }
+}
+
 fn render_flamegraph(fg: &mut FlamegraphTab, frame: &mut Frame, area: Rect) {
     let buf = frame.buffer_mut();
 
@@ -400,21 +636,19 @@ fn render_flamegraph(fg: &mut FlamegraphTab, frame: &mut Frame, area: Rect) {
         return;
     }
 
-    let zoom_root = get_zoom_node(&fg.graph.root, &fg.zoom_path);
-    if zoom_root.total_value <= 0 {
+    let tree = fg.inverted.as_ref().unwrap_or(&fg.graph.root);
+    let zoom_root = get_zoom_node(tree, &fg.zoom_path);
+    let root_total = tree.node(zoom_root).total_value;
+    if root_total <= 0 {
         render_empty_fg(buf, area);
         return;
     }
 
-    let forced_palette = fg
-        .zoom_path
-        .first()
-        .map(|name| thread_rank(&fg.graph.root, name));
+    let forced_palette = fg.zoom_path.first().map(|&name| thread_rank(tree, name));
 
-    let frames = layout_frames(zoom_root, area.width, forced_palette);
+    let frames = layout_frames(tree, zoom_root, area.width, forced_palette);
     let max_depth = frames.iter().map(|f| f.depth).max().unwrap_or(0);
     let viewport_height = area.height as usize;
-    let root_total = zoom_root.total_value;
 
     let cursor_depth = fg.cursor_path.len();
     if viewport_height > 0 {
@@ -429,10 +663,11 @@ fn render_flamegraph(fg: &mut FlamegraphTab, frame: &mut Frame, area: Rect) {
         .scroll_y
         .min(max_depth.saturating_sub(viewport_height.saturating_sub(1)));
 
-    let cursor_rect = cursor_frame_rect(zoom_root, &fg.cursor_path, area.width, forced_palette);
+    let cursor_rect =
+        cursor_frame_rect(tree, zoom_root, &fg.cursor_path, area.width, forced_palette);
 
     if let Some(ref cr) = cursor_rect {
-        fg.selection.name = cr.name.clone();
+        fg.selection.name = fg.graph.name(cr.name).to_string();
         fg.selection.self_value = cr.self_value;
         fg.selection.total_value = cr.total_value;
         fg.selection.pct = if root_total > 0 {
@@ -441,6 +676,8 @@ fn render_flamegraph(fg: &mut FlamegraphTab, frame: &mut Frame, area: Rect) {
             0.0
         };
         fg.selection.depth = cr.depth;
+        fg.selection.inlined = cr.inlined;
+        fg.selection.baseline = fg.diff.as_ref().and_then(|d| d.baseline(cr.node));
     }
 
     for fr in &frames {
@@ -467,76 +704,14 @@ fn render_flamegraph(fg: &mut FlamegraphTab, frame: &mut Frame, area: Rect) {
             0.0
         };
 
-        let bg = if is_cursor {
-            lighten(flame_color(&fr.name, heat, fr.palette_index), 45)
-        } else {
-            flame_color(&fr.name, heat, fr.palette_index)
+        let fr_name = fg.graph.name(fr.name);
+        let base = match &fg.diff {
+            Some(diff) => diff_color(diff.change(fr.node, fr.total_value)),
+            None => flame_color(fr_name, heat, fr.palette_index),
         };
-        let fg_color = contrast_fg(bg);
-
-        let x_start = area.x + fr.x;
-        let x_end = (area.x + fr.x + fr.width).min(area.x + area.width);
-        let border_color = darken(bg, 55);
-
-        for x in x_start..x_end {
-            if let Some(cell) = buf.cell_mut((x, screen_y)) {
-                if x == x_start || x == x_end.saturating_sub(1) {
-                    cell.set_char('▏');
-                    cell.set_style(Style::default().fg(border_color).bg(bg));
-                } else {
-                    cell.set_char(' ');
-                    cell.set_style(Style::default().bg(bg));
-                }
-            }
-        }
-
-        let inner_width = fr.width.saturating_sub(2);
-        if inner_width >= 3 {
-            let max_chars = inner_width as usize;
-            let name = truncate(&fr.name, max_chars);
-            let pad = (inner_width as usize).saturating_sub(name.len()) / 2;
-            let name_x = area.x + fr.x + 1 + pad as u16;
-
-            let style = if is_cursor {
-                Style::default()
-                    .fg(fg_color)
-                    .bg(bg)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().fg(fg_color).bg(bg)
-            };
-            buf.set_string(name_x, screen_y, &name, style);
-        }
-
-        if fr.width >= 14 && root_total > 0 {
-            let pct = fr.total_value as f64 / root_total as f64 * 100.0;
-            if pct >= 0.1 {
-                let pct_str = format!("{:.1}%", pct);
-                let pct_x = area.x + fr.x + fr.width - pct_str.len() as u16 - 2;
-                if pct_x > area.x + fr.x + 2 {
-                    let dim_fg = blend(fg_color, bg, 0.45);
-                    buf.set_string(
-                        pct_x,
-                        screen_y,
-                        &pct_str,
-                        Style::default().fg(dim_fg).bg(bg),
-                    );
-                }
-            }
-        }
-
-        if is_cursor
-            && fr.width >= 3
-            && let Some(cell) = buf.cell_mut((area.x + fr.x + 1, screen_y))
-        {
-            cell.set_char('▸');
-            cell.set_style(
-                Style::default()
-                    .fg(Color::White)
-                    .bg(bg)
-                    .add_modifier(Modifier::BOLD),
-            );
-        }
+        let bg = if is_cursor { lighten(base, 45) } else { base };
+        let row = Rect::new(area.x, screen_y, area.width, 1);
+        draw_frame(buf, row, fr, fr_name, bg, is_cursor, root_total);
     }
 
     for vis_d in 0..viewport_height {
@@ -557,6 +732,81 @@ fn render_flamegraph(fg: &mut FlamegraphTab, frame: &mut Frame, area: Rect) {
     }
 }
 
+/// Paint one frame of a flamegraph onto a row of the screen.
+fn draw_frame(
+    buf: &mut Buffer,
+    row: Rect,
+    fr: &FrameRect,
+    fr_name: &str,
+    bg: Color,
+    is_cursor: bool,
+    root_total: i64,
+) {
+    let fg_color = contrast_fg(bg);
+
+    let x_start = row.x + fr.x;
+    let x_end = (row.x + fr.x + fr.width).min(row.x + row.width);
+    let border_color = darken(bg, 55);
+
+    for x in x_start..x_end {
+        if let Some(cell) = buf.cell_mut((x, row.y)) {
+            if x == x_start || x == x_end.saturating_sub(1) {
+                cell.set_char('▏');
+                cell.set_style(Style::default().fg(border_color).bg(bg));
+            } else {
+                cell.set_char(' ');
+                cell.set_style(Style::default().bg(bg));
+            }
+        }
+    }
+
+    let inner_width = fr.width.saturating_sub(2);
+    if inner_width >= 3 {
+        let max_chars = inner_width as usize;
+        let name = truncate(fr_name, max_chars);
+        let pad = (inner_width as usize).saturating_sub(name.len()) / 2;
+        let name_x = row.x + fr.x + 1 + pad as u16;
+
+        let mut style = if is_cursor {
+            Style::default()
+                .fg(fg_color)
+                .bg(bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(fg_color).bg(bg)
+        };
+        if fr.inlined {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        buf.set_string(name_x, row.y, &name, style);
+    }
+
+    if fr.width >= 14 && root_total > 0 {
+        let pct = fr.total_value as f64 / root_total as f64 * 100.0;
+        if pct >= 0.1 {
+            let pct_str = format!("{:.1}%", pct);
+            let pct_x = row.x + fr.x + fr.width - pct_str.len() as u16 - 2;
+            if pct_x > row.x + fr.x + 2 {
+                let dim_fg = blend(fg_color, bg, 0.45);
+                buf.set_string(pct_x, row.y, &pct_str, Style::default().fg(dim_fg).bg(bg));
+            }
+        }
+    }
+
+    if is_cursor
+        && fr.width >= 3
+        && let Some(cell) = buf.cell_mut((row.x + fr.x + 1, row.y))
+    {
+        cell.set_char('▸');
+        cell.set_style(
+            Style::default()
+                .fg(Color::White)
+                .bg(bg)
+                .add_modifier(Modifier::BOLD),
+        );
+    }
+}
+
 fn render_empty_fg(buf: &mut Buffer, area: Rect) {
     let msg = "No profile data yet";
     let y = area.y + area.height / 2;
@@ -722,10 +972,212 @@ fn render_flamescope(fs: &mut FlamescopeTab, frame: &mut Frame, area: Rect) {
     }
 }
 
-fn render_exe_status_bar(status: Option<&str>, frame: &mut Frame, area: Rect) {
-    let Some(status) = status else { return };
+fn render_functions_bar(top: &FunctionsTab, frame: &mut Frame, area: Rect) {
+    let sep = " │ ".fg(Color::Rgb(55, 55, 65));
+    let mut spans = vec![
+        " functions: ".fg(DIM),
+        top.visible.len().to_string().fg(Color::Rgb(130, 130, 150)),
+        sep.clone(),
+        "sort: ".fg(DIM),
+        format!(
+            "{} {}",
+            top.sort.label(),
+            if top.reversed { "▲" } else { "▼" }
+        )
+        .fg(ACCENT)
+        .bold(),
+    ];
+    if top.filter.active || !top.filter.input.is_empty() {
+        spans.push(sep);
+        spans.push("filter: ".fg(DIM));
+        spans.push(top.filter.input.clone().fg(BRIGHT));
+        if top.filter.active {
+            spans.push("▏".fg(ACCENT));
+        }
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn render_functions_table(top: &mut FunctionsTab, frame: &mut Frame, area: Rect) {
+    let buf = frame.buffer_mut();
+
+    if area.height < 2 {
+        return;
+    }
+
+    let num_w = 10u16;
+    let pct_w = 8u16;
+    let paths_w = 8u16;
+    let col_self_x = area.x + 1;
+    let col_self_pct_x = col_self_x + num_w;
+    let col_total_x = col_self_pct_x + pct_w;
+    let col_total_pct_x = col_total_x + num_w;
+    let col_paths_x = col_total_pct_x + pct_w;
+    let col_name_x = col_paths_x + paths_w;
+    let col_name_w = (area.x + area.width).saturating_sub(col_name_x + 1) as usize;
+
+    let hdr_style = Style::default().fg(DIM).add_modifier(Modifier::BOLD);
+    let sorted_style = Style::default().fg(ACCENT).add_modifier(Modifier::BOLD);
+    let header = [
+        (col_self_x, "Self", Some(SortColumn::SelfValue)),
+        (col_self_pct_x, "Self%", None),
+        (col_total_x, "Total", Some(SortColumn::Total)),
+        (col_total_pct_x, "Total%", None),
+        (col_paths_x, "Paths", Some(SortColumn::CallPaths)),
+        (col_name_x, "Function", Some(SortColumn::Name)),
+    ];
+    for (x, label, column) in header {
+        let style = if column == Some(top.sort) {
+            sorted_style
+        } else {
+            hdr_style
+        };
+        buf.set_string(x, area.y, label, style);
+    }
+
+    let sep_y = area.y + 1;
+    for x in area.x..area.x + area.width {
+        if let Some(c) = buf.cell_mut((x, sep_y)) {
+            c.set_char('─');
+            c.set_style(Style::default().fg(SEP_COLOR));
+        }
+    }
+
+    let visible_rows = (area.y + area.height).saturating_sub(sep_y + 1) as usize;
+    if visible_rows == 0 {
+        return;
+    }
+    if top.visible.is_empty() {
+        let msg = if top.filter.input.is_empty() {
+            "No profile data yet"
+        } else {
+            "No matching functions"
+        };
+        buf.set_string(
+            area.x + 1,
+            sep_y + 1,
+            msg,
+            Style::default()
+                .fg(Color::Rgb(90, 90, 110))
+                .add_modifier(Modifier::ITALIC),
+        );
+        return;
+    }
+
+    if top.cursor < top.scroll {
+        top.scroll = top.cursor;
+    }
+    if top.cursor >= top.scroll + visible_rows {
+        top.scroll = top.cursor + 1 - visible_rows;
+    }
+
+    let cursor_bg = Color::Rgb(40, 45, 65);
+    let pct = |v: i64| format!("{:.2}%", share(v, top.total) * 100.0);
+
+    for vis_row in 0..visible_rows {
+        let idx = top.scroll + vis_row;
+        if idx >= top.visible.len() {
+            break;
+        }
+        let row = top.row(idx);
+        let y = sep_y + 1 + vis_row as u16;
+        let is_cursor = idx == top.cursor;
+        let row_bg = if is_cursor { cursor_bg } else { Color::Reset };
+
+        if is_cursor {
+            for x in area.x..area.x + area.width {
+                if let Some(c) = buf.cell_mut((x, y)) {
+                    c.set_char(' ');
+                    c.set_style(Style::default().bg(cursor_bg));
+                }
+            }
+        }
+
+        let self_style = Style::default().fg(Color::Rgb(249, 115, 22)).bg(row_bg);
+        let total_style = Style::default().fg(Color::Rgb(234, 179, 8)).bg(row_bg);
+        let dim_style = Style::default().fg(Color::Rgb(130, 130, 150)).bg(row_bg);
+        buf.set_string(
+            col_self_x,
+            y,
+            format_count(row.self_value as u64),
+            self_style,
+        );
+        buf.set_string(col_self_pct_x, y, pct(row.self_value), dim_style);
+        buf.set_string(
+            col_total_x,
+            y,
+            format_count(row.total_value as u64),
+            total_style,
+        );
+        buf.set_string(col_total_pct_x, y, pct(row.total_value), dim_style);
+        buf.set_string(col_paths_x, y, row.call_paths.to_string(), dim_style);
+
+        let prefix = if is_cursor { "▸ " } else { "  " };
+        let name = truncate(&row.label, col_name_w.saturating_sub(prefix.len()));
+        let name_style = if is_cursor {
+            Style::default()
+                .fg(BRIGHT)
+                .bg(row_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Rgb(180, 180, 195)).bg(row_bg)
+        };
+        buf.set_string(col_name_x, y, format!("{prefix}{name}"), name_style);
+    }
+}
+
+fn render_exe_status_bar(exe: &ExecutablesTab, frame: &mut Frame, area: Rect) {
+    let usage = &exe.usage;
+    let mut usage_spans = Vec::new();
+    let (queued, running) = exe.jobs.pending();
+    if queued + running > 0 {
+        usage_spans.push("jobs: ".fg(DIM));
+        usage_spans
+            .push(format!("{} running · {} queued", running, queued).fg(Color::Rgb(234, 179, 8)));
+        usage_spans.push(" │ ".fg(Color::Rgb(55, 55, 65)));
+    }
+    usage_spans.push("store: ".fg(DIM));
+    usage_spans.push(format_bytes(usage.total_bytes).fg(Color::Rgb(130, 130, 150)));
+    if let Some(quota) = usage.quota {
+        let color = if usage.total_bytes > quota {
+            Color::Rgb(239, 68, 68)
+        } else {
+            Color::Rgb(130, 130, 150)
+        };
+        usage_spans.push(" / ".fg(DIM));
+        usage_spans.push(format_bytes(quota).fg(color));
+    }
+    usage_spans.push(" │ ".fg(Color::Rgb(55, 55, 65)));
+    usage_spans.push("disk: ".fg(DIM));
+    usage_spans.push(format_bytes(usage.disk_bytes).fg(Color::Rgb(130, 130, 150)));
+    if let Some(rate) = usage.cache.frame_hit_rate() {
+        let string_rate = usage.cache.string_hit_rate().unwrap_or(0.0);
+        usage_spans.push(" │ ".fg(Color::Rgb(55, 55, 65)));
+        usage_spans.push("cache: ".fg(DIM));
+        usage_spans.push(
+            format!(
+                "{} frames {:.1}% · {} strings {:.1}%",
+                format_count(usage.cache.frame_entries as u64),
+                rate * 100.0,
+                format_count(usage.cache.string_entries as u64),
+                string_rate * 100.0,
+            )
+            .fg(Color::Rgb(130, 130, 150)),
+        );
+    }
+    usage_spans.push(" ".into());
+    frame.render_widget(
+        Paragraph::new(Line::from(usage_spans)).alignment(Alignment::Right),
+        area,
+    );
+
+    let Some(status) = exe.status.as_deref() else {
+        return;
+    };
 
-    let is_loading = status.starts_with("Loading") || status.starts_with("Removing");
+    let is_loading = status.starts_with("Loading")
+        || status.starts_with("Removing")
+        || status.starts_with("Compacting");
     let is_error = status.starts_with("Error");
 
     let display = if is_loading {
@@ -759,18 +1211,25 @@ fn render_exe_table(exe: &mut ExecutablesTab, frame: &mut Frame, area: Rect) {
 
     let header_y = area.y;
     let col_id_w = 34u16.min(area.width / 3);
-    let col_sym_w = 12u16;
-    let col_name_w = area.width.saturating_sub(col_id_w + col_sym_w + 4);
+    let col_arch_w = 10u16;
+    let col_sym_w = 10u16;
+    let col_size_w = 10u16;
+    let col_used_w = 10u16;
+    let col_name_w = area
+        .width
+        .saturating_sub(col_id_w + col_arch_w + col_sym_w + col_size_w + col_used_w + 4);
+    let col_arch_x = area.x + 1 + col_id_w + col_name_w;
+    let col_sym_x = col_arch_x + col_arch_w;
+    let col_size_x = col_sym_x + col_sym_w;
+    let col_used_x = col_size_x + col_size_w;
 
     let hdr_style = Style::default().fg(DIM).add_modifier(Modifier::BOLD);
     buf.set_string(area.x + 1, header_y, "File ID", hdr_style);
     buf.set_string(area.x + 1 + col_id_w, header_y, "Name", hdr_style);
-    buf.set_string(
-        area.x + 1 + col_id_w + col_name_w,
-        header_y,
-        "Symbols",
-        hdr_style,
-    );
+    buf.set_string(col_arch_x, header_y, "Arch", hdr_style);
+    buf.set_string(col_sym_x, header_y, "Symbols", hdr_style);
+    buf.set_string(col_size_x, header_y, "Size", hdr_style);
+    buf.set_string(col_used_x, header_y, "Last used", hdr_style);
 
     let sep_y = header_y + 1;
     if sep_y >= area.y + area.height {
@@ -844,9 +1303,18 @@ fn render_exe_table(exe: &mut ExecutablesTab, frame: &mut Frame, area: Rect) {
 
         let prefix = if is_cursor { "▸ " } else { "  " };
         let max_name = (col_name_w as usize).saturating_sub(prefix.len() + 1);
-        let name = truncate(&entry.name, max_name);
+        let name = if entry.stale {
+            format!(
+                "{} [stale]",
+                truncate(&entry.name, max_name.saturating_sub(8))
+            )
+        } else {
+            truncate(&entry.name, max_name)
+        };
         let name_fg = if is_cursor {
             BRIGHT
+        } else if entry.stale {
+            Color::Rgb(245, 166, 35)
         } else if is_sym {
             Color::Rgb(180, 180, 195)
         } else {
@@ -867,6 +1335,26 @@ fn render_exe_table(exe: &mut ExecutablesTab, frame: &mut Frame, area: Rect) {
             name_style,
         );
 
+        let arch_str = entry
+            .details
+            .as_ref()
+            .filter(|d| !d.arch.is_empty())
+            .map_or("N/A".to_string(), |d| {
+                truncate(&d.arch, col_arch_w as usize - 1)
+            });
+        buf.set_string(
+            col_arch_x,
+            y,
+            &arch_str,
+            Style::default()
+                .fg(if is_sym {
+                    Color::Rgb(130, 130, 150)
+                } else {
+                    na_fg
+                })
+                .bg(row_bg),
+        );
+
         let sym_str = entry
             .num_ranges
             .map_or("N/A".to_string(), |n| format_count(n as u64));
@@ -876,11 +1364,32 @@ fn render_exe_table(exe: &mut ExecutablesTab, frame: &mut Frame, area: Rect) {
             na_fg
         };
         buf.set_string(
-            area.x + 1 + col_id_w + col_name_w,
+            col_sym_x,
             y,
             &sym_str,
             Style::default().fg(sym_fg).bg(row_bg),
         );
+
+        let size_str = entry.size_bytes.map_or("N/A".to_string(), format_bytes);
+        let stat_fg = if is_sym {
+            Color::Rgb(130, 130, 150)
+        } else {
+            na_fg
+        };
+        buf.set_string(
+            col_size_x,
+            y,
+            &size_str,
+            Style::default().fg(stat_fg).bg(row_bg),
+        );
+
+        let used_str = entry.last_lookup.map_or("N/A".to_string(), format_age);
+        buf.set_string(
+            col_used_x,
+            y,
+            &used_str,
+            Style::default().fg(stat_fg).bg(row_bg),
+        );
     }
 
     if exe.list.is_empty() && !exe.path_input.active {
@@ -897,6 +1406,190 @@ fn render_exe_table(exe: &mut ExecutablesTab, frame: &mut Frame, area: Rect) {
     }
 }
 
+fn render_jobs_panel(jobs: &mut JobsPanel, frame: &mut Frame, area: Rect) {
+    let buf = frame.buffer_mut();
+    if area.height < 2 {
+        return;
+    }
+
+    let title = " jobs ";
+    for x in area.x..area.x + area.width {
+        if let Some(c) = buf.cell_mut((x, area.y)) {
+            c.set_char('─');
+            c.set_style(Style::default().fg(ACCENT));
+        }
+    }
+    buf.set_string(
+        area.x + 2,
+        area.y,
+        title,
+        Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+    );
+
+    let visible_rows = (area.height - 1) as usize;
+    if jobs.cursor < jobs.scroll {
+        jobs.scroll = jobs.cursor;
+    }
+    if jobs.cursor >= jobs.scroll + visible_rows {
+        jobs.scroll = jobs.cursor + 1 - visible_rows;
+    }
+
+    if jobs.list.is_empty() {
+        buf.set_string(
+            area.x + 2,
+            area.y + 1,
+            "No symbol loads yet",
+            Style::default().fg(DIM).add_modifier(Modifier::ITALIC),
+        );
+        return;
+    }
+
+    let cursor_bg = Color::Rgb(40, 45, 65);
+    let col_state_w = 36u16.min(area.width / 2);
+    let col_state_x = area.x + area.width.saturating_sub(col_state_w);
+    for (vis_row, job) in jobs
+        .list
+        .iter()
+        .skip(jobs.scroll)
+        .take(visible_rows)
+        .enumerate()
+    {
+        let y = area.y + 1 + vis_row as u16;
+        let is_cursor = jobs.scroll + vis_row == jobs.cursor;
+        let row_bg = if is_cursor { cursor_bg } else { Color::Reset };
+        if is_cursor {
+            for x in area.x..area.x + area.width {
+                if let Some(c) = buf.cell_mut((x, y)) {
+                    c.set_char(' ');
+                    c.set_style(Style::default().bg(cursor_bg));
+                }
+            }
+        }
+
+        let prefix = if is_cursor { "▸ " } else { "  " };
+        let max_name = ((col_state_x - area.x) as usize).saturating_sub(prefix.len() + 2);
+        let name_fg = if is_cursor {
+            BRIGHT
+        } else {
+            Color::Rgb(180, 180, 195)
+        };
+        buf.set_string(
+            area.x + 1,
+            y,
+            format!("{prefix}{}", truncate(&job.name, max_name)),
+            Style::default().fg(name_fg).bg(row_bg),
+        );
+
+        let (state_str, state_fg) = match &job.state {
+            JobState::Queued => ("queued".to_string(), DIM),
+            JobState::Running { phase, ranges } => {
+                let phase = match phase {
+                    Phase::Parsing => "parsing",
+                    Phase::Extracting => "extracting",
+                    Phase::Storing => "storing",
+                };
+                let progress = if *ranges > 0 {
+                    format!("{phase} · {} ranges", format_count(*ranges as u64))
+                } else {
+                    format!("{phase}...")
+                };
+                (progress, Color::Rgb(234, 179, 8))
+            }
+            JobState::Done { ranges } => (
+                format!("done · {} ranges", format_count(*ranges as u64)),
+                Color::Rgb(34, 197, 94),
+            ),
+            JobState::Failed(err) => (format!("failed: {err}"), Color::Rgb(239, 68, 68)),
+            JobState::Cancelled => ("cancelled".to_string(), DIM),
+        };
+        buf.set_string(
+            col_state_x,
+            y,
+            truncate(&state_str, (col_state_w as usize).saturating_sub(1)),
+            Style::default().fg(state_fg).bg(row_bg),
+        );
+    }
+}
+
+fn render_exe_detail(exe: &ExecutablesTab, frame: &mut Frame, area: Rect) {
+    let Some(entry) = exe.list.get(exe.cursor) else {
+        return;
+    };
+    let buf = frame.buffer_mut();
+
+    let na = || "N/A".to_string();
+    let details = entry.details.as_ref();
+    let rows: Vec<(&str, String)> = vec![
+        (
+            "File ID",
+            entry.file_id.map_or_else(na, |id| id.format_hex()),
+        ),
+        ("Path", details.map_or_else(na, |d| d.path.clone())),
+        (
+            "State",
+            if entry.stale {
+                "stale (binary changed on disk)".to_string()
+            } else if entry.num_ranges.is_some() {
+                "current".to_string()
+            } else {
+                na()
+            },
+        ),
+        (
+            "Build ID",
+            details.and_then(|d| d.build_id.clone()).unwrap_or_else(na),
+        ),
+        ("Arch", details.map_or_else(na, |d| d.arch.clone())),
+        (
+            "File size",
+            details.map_or_else(na, |d| format_bytes(d.file_size)),
+        ),
+        ("Modified", details.map_or_else(na, |d| format_age(d.mtime))),
+        (
+            "Imported",
+            details.map_or_else(na, |d| format_age(d.imported_at)),
+        ),
+        (
+            "Extractors",
+            details.map_or_else(na, |d| d.extractors.join(", ")),
+        ),
+        (
+            "Symbols",
+            entry.num_ranges.map_or_else(na, |n| format_count(n as u64)),
+        ),
+        ("Store size", entry.size_bytes.map_or_else(na, format_bytes)),
+        ("Last used", entry.last_lookup.map_or_else(na, format_age)),
+    ];
+
+    let popup_w = 80u16.min(area.width.saturating_sub(4));
+    let popup_h = (rows.len() as u16 + 2).min(area.height);
+    if popup_w < 24 || popup_h < 3 {
+        return;
+    }
+    let popup = Rect::new(
+        area.x + (area.width - popup_w) / 2,
+        area.y + (area.height - popup_h) / 2,
+        popup_w,
+        popup_h,
+    );
+    clear_rect(buf, popup);
+    let title = format!(" {} ", truncate(&entry.name, popup_w as usize - 6));
+    draw_popup_border(buf, popup, &title, ACCENT);
+
+    let label_w = 12u16;
+    let value_w = popup.width.saturating_sub(label_w + 4) as usize;
+    for (i, (label, value)) in rows.iter().take(popup_h as usize - 2).enumerate() {
+        let y = popup.y + 1 + i as u16;
+        buf.set_string(popup.x + 2, y, label, Style::reset().fg(DIM));
+        buf.set_string(
+            popup.x + 2 + label_w,
+            y,
+            truncate(value, value_w),
+            Style::reset().fg(BRIGHT),
+        );
+    }
+}
+
 struct OverlayProps<'a> {
     title: &'a str,
     input: &'a str,
@@ -1069,9 +1762,46 @@ const FLAMEGRAPH_KEYS: &[(&str, &str)] = &[
     ("[Enter]", " zoom "),
     ("[Esc]", " back "),
     ("[/]", " search "),
+    ("[i]", " invert "),
+    ("[b]", " baseline "),
+    ("[d]", " diff "),
+    ("[E]", " export "),
+    ("[s]", " sandwich "),
+    ("[t/T]", " threads "),
+    ("[R]", " rules "),
+    ("[c]", " recursion "),
+    ("[F/I]", " focus/ignore "),
+    ("[o]", " re-root "),
+    ("[O]", " order "),
+    ("[n]", " inlines "),
+    ("[w]", " window "),
     ("[r]", " reset "),
 ];
 
+const FUNCTIONS_KEYS: &[(&str, &str)] = &[
+    ("[Tab]", " switch "),
+    ("[q]", " quit "),
+    ("[j/↓ k/↑]", " navigate "),
+    ("[s]", " sort "),
+    ("[S]", " reverse "),
+    ("[/]", " filter "),
+    ("[Esc]", " clear filter "),
+    ("[Enter]", " show in flamegraph "),
+];
+
+const FILTER_KEYS: &[(&str, &str)] = &[
+    ("[Esc]", " cancel "),
+    ("[Enter]", " done "),
+    ("[Backspace]", " delete "),
+];
+
+const SANDWICH_KEYS: &[(&str, &str)] = &[
+    ("[Tab]", " switch "),
+    ("[q]", " quit "),
+    ("[f/Space]", " freeze "),
+    ("[s/Esc]", " close "),
+];
+
 const SEARCH_KEYS: &[(&str, &str)] = &[
     ("[Esc]", " cancel "),
     ("[Enter]", " select "),
@@ -1094,10 +1824,21 @@ const EXE_KEYS: &[(&str, &str)] = &[
     ("[j/k]", " navigate "),
     ("[Enter]", " symbolize "),
     ("[r]", " remove "),
+    ("[c]", " compact "),
     ("[/]", " add new "),
+    ("[p]", " load pid "),
+    ("[J]", " jobs "),
+    ("[i]", " info "),
     ("[q]", " quit "),
 ];
 
+const JOBS_KEYS: &[(&str, &str)] = &[
+    ("[Esc]", " close "),
+    ("[j/k]", " navigate "),
+    ("[x]", " cancel "),
+    ("[C]", " clear finished "),
+];
+
 const EXE_INPUT_KEYS: &[(&str, &str)] = &[
     ("[Esc]", " cancel "),
     ("[Tab]", " complete "),
@@ -1105,6 +1846,10 @@ const EXE_INPUT_KEYS: &[(&str, &str)] = &[
     ("[Enter]", " load "),
 ];
 
+const DETAIL_KEYS: &[(&str, &str)] = &[("[Esc/i]", " close ")];
+
+const PID_INPUT_KEYS: &[(&str, &str)] = &[("[Esc]", " cancel "), ("[Enter]", " load ")];
+
 fn render_keyhints(
     overlay_active: bool,
     normal: &[(&str, &str)],
@@ -1131,7 +1876,10 @@ fn render_keyhints(
     );
 }
 
-const PALETTES: &[&[(f64, (u8, u8, u8))]] = &[
+/// Color stops of a gradient: position from 0 to 1 and RGB color.
+type Palette = &'static [(f64, (u8, u8, u8))];
+
+const PALETTES: &[Palette] = &[
     &[
         (0.00, (253, 224, 71)),
         (0.25, (251, 191, 36)),
@@ -1216,6 +1964,17 @@ fn flame_color(name: &str, heat: f64, palette_index: usize) -> Color {
     )
 }
 
+/// Red for frames that grew since the baseline, blue for frames that
+/// shrank, grey when unchanged; `change` ranges from -1 to 1.
+fn diff_color(change: f64) -> Color {
+    let neutral = Color::Rgb(80, 80, 92);
+    if change >= 0.0 {
+        blend(neutral, Color::Rgb(220, 38, 38), change.min(1.0))
+    } else {
+        blend(neutral, Color::Rgb(37, 99, 235), (-change).min(1.0))
+    }
+}
+
 fn gradient(t: f64, stops: &[(f64, (u8, u8, u8))]) -> (u8, u8, u8) {
     let t = t.clamp(0.0, 1.0);
     for i in 0..stops.len() - 1 {
@@ -1311,6 +2070,41 @@ fn truncate(s: &str, max: usize) -> String {
     }
 }
 
+fn format_bytes(n: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{n} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Render a unix timestamp (seconds) as a coarse "time ago" string.
+fn format_age(ts: u64) -> String {
+    if ts == 0 {
+        return "never".to_string();
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let secs = now.saturating_sub(ts);
+    if secs < 60 {
+        "just now".to_string()
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86_400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86_400)
+    }
+}
+
 fn format_count(n: u64) -> String {
     if n >= 1_000_000 {
         format!("{:.1}M", n as f64 / 1_000_000.0)